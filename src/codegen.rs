@@ -0,0 +1,320 @@
+use lexicon::{
+    AtpInteger, AtpObject, AtpParams, AtpString, AtpTypes, Catalog, Lexicon, ParamProps, RpcIO,
+    RpcSchema,
+};
+
+// typescript declarations for a lexicon, one type per def
+// refs into lexicons the catalog knows are imported from the file generated for them,
+// anything else becomes `unknown`
+// two defs that end up with the same typescript name are an error, nothing is generated then
+pub fn typescript(lexicon: &Lexicon, catalog: &Catalog) -> Result<String, Vec<String>> {
+    let mut scope = Scope {
+        lexicon,
+        catalog,
+        imports: Vec::new(),
+        def: String::new(),
+        declared: Vec::new(),
+        errors: Vec::new(),
+    };
+    let mut out = String::new();
+
+    for (name, def) in &lexicon.defs {
+        out.push('\n');
+        scope.def = name.clone();
+        let ident = ident(&lexicon.id, name);
+        match def {
+            AtpTypes::Object(x) => interface(&mut out, &ident, x, &mut scope),
+            AtpTypes::Record(x) => interface(&mut out, &ident, &x.record, &mut scope),
+            AtpTypes::Token(_) => {
+                scope.declare(&ident);
+                out.push_str(&format!(
+                    "export const {ident} = \"{}#{name}\";\n",
+                    lexicon.id
+                ))
+            }
+            AtpTypes::Query(x) => {
+                params(&mut out, &ident, &x.parameters, &mut scope);
+                io(&mut out, &format!("{ident}Output"), &x.output, &mut scope);
+            }
+            AtpTypes::Procedure(x) => {
+                params(&mut out, &ident, &x.parameters, &mut scope);
+                io(&mut out, &format!("{ident}Input"), &x.input, &mut scope);
+                io(&mut out, &format!("{ident}Output"), &x.output, &mut scope);
+            }
+            AtpTypes::Subscription(x) => {
+                params(&mut out, &ident, &x.parameters, &mut scope);
+                scope.declare(&format!("{ident}Message"));
+                out.push_str(&format!(
+                    "export type {ident}Message = {};\n",
+                    refs(&x.message.schema.refs, &mut scope)
+                ));
+            }
+            other => {
+                scope.declare(&ident);
+                out.push_str(&format!(
+                    "export type {ident} = {};\n",
+                    ts(other, &mut scope)
+                ))
+            }
+        }
+    }
+    if !scope.errors.is_empty() {
+        return Err(scope.errors);
+    }
+
+    let mut header = format!("// generated from {}, do not edit\n", lexicon.id);
+    if !scope.imports.is_empty() {
+        header.push('\n');
+    }
+    for nsid in &scope.imports {
+        header.push_str(&format!(
+            "import type * as {} from \"{}\";\n",
+            namespace(nsid),
+            import_path(&lexicon.id, nsid)
+        ));
+    }
+    Ok(header + &out)
+}
+
+// the lexicon being generated and the other lexicons its refs have pulled in so far
+struct Scope<'a> {
+    lexicon: &'a Lexicon,
+    catalog: &'a Catalog,
+    imports: Vec<String>,
+    // the def being generated, and every typescript name taken so far with the def that took it
+    def: String,
+    declared: Vec<(String, String)>,
+    errors: Vec<String>,
+}
+
+impl Scope<'_> {
+    // `main` of `com.example.feed.post` and a def called `post` are both `Post`
+    fn declare(&mut self, ident: &str) {
+        match self.declared.iter().find(|(x, _)| x == ident) {
+            Some((_, def)) => self.errors.push(format!(
+                "`{}` and `{def}` are both `{ident}` in typescript, rename one of them",
+                self.def
+            )),
+            None => self.declared.push((ident.to_string(), self.def.clone())),
+        }
+    }
+}
+
+// `main` is named after the last nsid segment, everything else after the def
+fn ident(nsid: &str, name: &str) -> String {
+    if name == "main" {
+        pascal(nsid.rsplit('.').next().unwrap_or(name))
+    } else {
+        pascal(name)
+    }
+}
+
+// what another lexicon is imported as, `com.atproto.repo.strongRef` is `ComAtprotoRepoStrongRef`
+fn namespace(nsid: &str) -> String {
+    nsid.split('.').map(pascal).collect()
+}
+
+// the files are laid out by nsid, `com.example.feed` is `com/example/feed.ts`
+fn import_path(from: &str, to: &str) -> String {
+    let depth = from.split('.').count() - 1;
+    format!("{}{}", "../".repeat(depth), to.replace('.', "/"))
+}
+
+fn pascal(s: &str) -> String {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .map(|x| x.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+fn interface(out: &mut String, ident: &str, object: &AtpObject, scope: &mut Scope) {
+    scope.declare(ident);
+    out.push_str(&format!("export interface {ident} {{\n"));
+    let required = object.required.clone().unwrap_or_default();
+    let nullable = object.nullable.clone().unwrap_or_default();
+    for (name, t) in &object.properties {
+        let marker = if required.contains(name) { "" } else { "?" };
+        let null = if nullable.contains(name) {
+            " | null"
+        } else {
+            ""
+        };
+        out.push_str(&format!("  {name}{marker}: {}{null};\n", ts(t, scope)));
+    }
+    out.push_str("}\n");
+}
+
+fn params(out: &mut String, ident: &str, params: &Option<AtpParams>, scope: &mut Scope) {
+    let Some(params) = params else {
+        return;
+    };
+    scope.declare(&format!("{ident}Params"));
+    out.push_str(&format!("export interface {ident}Params {{\n"));
+    let required = params.required.clone().unwrap_or_default();
+    for (name, t) in &params.properties {
+        let marker = if required.contains(name) { "" } else { "?" };
+        out.push_str(&format!("  {name}{marker}: {};\n", ts_param(t)));
+    }
+    out.push_str("}\n");
+}
+
+fn io(out: &mut String, ident: &str, io: &Option<RpcIO>, scope: &mut Scope) {
+    let Some(schema) = io.as_ref().and_then(|x| x.schema.as_ref()) else {
+        return;
+    };
+    match schema {
+        RpcSchema::Object(x) => interface(out, ident, x, scope),
+        RpcSchema::Ref(x) => {
+            scope.declare(ident);
+            out.push_str(&format!(
+                "export type {ident} = {};\n",
+                reference(&x.reference, scope)
+            ))
+        }
+        RpcSchema::Union(x) => {
+            scope.declare(ident);
+            out.push_str(&format!(
+                "export type {ident} = {};\n",
+                refs(&x.refs, scope)
+            ))
+        }
+    }
+}
+
+fn ts(t: &AtpTypes, scope: &mut Scope) -> String {
+    match t {
+        AtpTypes::Null(_) => "null".to_string(),
+        AtpTypes::Boolean(_) => "boolean".to_string(),
+        AtpTypes::Integer(x) => integer(x),
+        AtpTypes::String(x) => string(x),
+        AtpTypes::Bytes(_) => "Uint8Array".to_string(),
+        AtpTypes::CidLink(_) => "{ $link: string }".to_string(),
+        AtpTypes::Blob(_) => {
+            "{ $type: \"blob\"; ref: { $link: string }; mimeType: string; size: number }"
+                .to_string()
+        }
+        AtpTypes::Array(x) => match x.items.as_ref() {
+            AtpTypes::Union(_) => format!("({})[]", ts(&x.items, scope)),
+            items => format!("{}[]", ts(items, scope)),
+        },
+        AtpTypes::Ref(x) => reference(&x.reference, scope),
+        AtpTypes::Union(x) => refs(&x.refs, scope),
+        _ => "unknown".to_string(),
+    }
+}
+
+fn ts_param(t: &ParamProps) -> String {
+    match t {
+        ParamProps::Boolean(_) => "boolean".to_string(),
+        ParamProps::Integer(x) => integer(x),
+        ParamProps::String(x) => string(x),
+        ParamProps::Unknown(_) => "unknown".to_string(),
+        ParamProps::Array { items, .. } => format!("{}[]", ts_param(items)),
+    }
+}
+
+fn integer(x: &AtpInteger) -> String {
+    match &x.enumeration {
+        Some(values) => literals(values.iter().map(|x| x.to_string())),
+        None => "number".to_string(),
+    }
+}
+
+fn string(x: &AtpString) -> String {
+    match &x.enumeration {
+        Some(values) => literals(values.iter().map(|x| format!("{x:?}"))),
+        None => "string".to_string(),
+    }
+}
+
+fn literals(values: impl Iterator<Item = String>) -> String {
+    values.collect::<Vec<_>>().join(" | ")
+}
+
+// named the same way as the def it points at, wherever that ends up
+fn reference(r: &str, scope: &mut Scope) -> String {
+    let (nsid, name) = r.split_once('#').unwrap_or((r, "main"));
+    if nsid.is_empty() || nsid == scope.lexicon.id {
+        return ident(&scope.lexicon.id, name);
+    }
+    if scope.catalog.resolve(r).is_none() {
+        return format!("unknown /* {r} */");
+    }
+    if !scope.imports.iter().any(|x| x == nsid) {
+        scope.imports.push(nsid.to_string());
+    }
+    format!("{}.{}", namespace(nsid), ident(nsid, name))
+}
+
+fn refs(refs: &[String], scope: &mut Scope) -> String {
+    if refs.is_empty() {
+        return "never".to_string();
+    }
+    refs.iter()
+        .map(|x| reference(x, scope))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lexicon(json: &str) -> Lexicon {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn pinksea() {
+        let lexicon = lexicon(&std::fs::read_to_string("pinksea.json").unwrap());
+        let out = typescript(&lexicon, &Catalog::new()).unwrap();
+        assert!(out.contains("export interface Oekaki {\n"));
+        assert!(out.contains("  createdAt: string;\n"));
+        assert!(out.contains("  image: Image;\n"));
+        assert!(out.contains("  tags?: string[];\n"));
+        assert!(out.contains("  inResponseTo?: unknown /* com.atproto.repo.strongRef */;\n"));
+    }
+    #[test]
+    fn refs_are_named_like_defs() {
+        let lexicon = lexicon(
+            r##"{ "lexicon": 1, "id": "com.example.feed.post", "defs": {
+                "main": { "type": "object", "properties": {} },
+                "reply": { "type": "object", "required": ["parent", "root"], "properties": {
+                    "parent": { "type": "ref", "ref": "#main" },
+                    "root": { "type": "ref", "ref": "com.example.feed.post" },
+                    "subject": { "type": "ref", "ref": "com.atproto.repo.strongRef" },
+                    "label": { "type": "ref", "ref": "com.example.missing#label" }
+                } }
+            } }"##,
+        );
+        let out = typescript(&lexicon, &Catalog::with_std()).unwrap();
+        assert!(out.contains(
+            "import type * as ComAtprotoRepoStrongRef from \"../../../com/atproto/repo/strongRef\";\n"
+        ));
+        assert!(out.contains("export interface Post {\n"));
+        assert!(out.contains("  parent: Post;\n"));
+        assert!(out.contains("  root: Post;\n"));
+        assert!(out.contains("  subject?: ComAtprotoRepoStrongRef.StrongRef;\n"));
+        assert!(out.contains("  label?: unknown /* com.example.missing#label */;\n"));
+    }
+
+    #[test]
+    fn colliding_names() {
+        let lexicon = lexicon(
+            r#"{ "lexicon": 1, "id": "com.example.feed.post", "defs": {
+                "main": { "type": "object", "properties": {} },
+                "post": { "type": "object", "properties": {} },
+                "getFeed": { "type": "query", "parameters": { "type": "params", "properties": {} } },
+                "getFeedParams": { "type": "object", "properties": {} }
+            } }"#,
+        );
+        assert_eq!(
+            typescript(&lexicon, &Catalog::new()),
+            Err(vec![
+                "`post` and `main` are both `Post` in typescript, rename one of them".to_string(),
+                "`getFeedParams` and `getFeed` are both `GetFeedParams` in typescript, rename one of them".to_string(),
+            ])
+        );
+    }
+}
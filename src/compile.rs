@@ -0,0 +1,799 @@
+use ir::{
+    diagnostic::{Diagnostic, syntax_errors},
+    props::{
+        GenericProp, PropKind,
+        alias::{Alias, Aliases, Target},
+        types::{generic::Template, object, string::KnownValue},
+    },
+};
+use lexicon::{
+    AtpArray, AtpObject, AtpParams, AtpProcedure, AtpQuery, AtpRecord, AtpRef, AtpString, AtpToken,
+    AtpTypes, AtpUnknown, Catalog, IndexMap, Lexicon, ParamProps, RecordKey, RpcError, RpcIO,
+    RpcSchema, StringFormats,
+};
+use tree_sitter::{Node, Range, Tree};
+
+use crate::{
+    error_sets::ErrorSets,
+    pagination::{self, Bounds},
+    spread::{self, Spread},
+};
+
+// lowers a parsed ana file into a lexicon, everything that goes wrong along the way ends up in the diagnostics
+// the lexicon is always returned so callers can still look at partial output
+// refs into lexicons the catalog knows about are checked, anything else is assumed to exist
+pub fn compile<'a>(
+    src: &'a str,
+    tree: &'a Tree,
+    catalog: &'a Catalog,
+) -> (Lexicon, Vec<Diagnostic>) {
+    let mut c = Compiler {
+        src,
+        catalog,
+        nsid: String::new(),
+        tokens: Vec::new(),
+        aliases: Aliases::default(),
+        names: Vec::new(),
+        hoisted: Vec::new(),
+        instances: IndexMap::new(),
+        error_sets: ErrorSets::default(),
+        spreads: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+    syntax_errors(&tree.root_node(), &mut c.diagnostics);
+
+    let mut res = Lexicon {
+        lexicon: 1,
+        id: String::new(),
+        revision: None,
+        description: None,
+        defs: IndexMap::new(),
+    };
+
+    let root = tree.root_node();
+    let mut cursor = root.walk();
+    let mut namespaces = root
+        .named_children(&mut cursor)
+        .filter(|x| x.kind() != "comment");
+
+    // only one namespace supported for now
+    let Some(namespace) = namespaces.next() else {
+        c.error(
+            "expected a namespace, e.g. `@com.example.foo { ... }`",
+            &root,
+        );
+        return (res, c.diagnostics);
+    };
+    for extra in namespaces {
+        c.error("only one namespace per file is supported", &extra);
+    }
+
+    match namespace.child_by_field_name("name") {
+        Some(nsid) => {
+            res.id = c.text(&nsid).to_string();
+            c.nsid = res.id.clone();
+        }
+        None => c.error("namespace is missing its nsid", &namespace),
+    }
+
+    c.aliases(&namespace);
+    c.error_sets(&namespace);
+
+    // inline objects are hoisted into defs, so every name that's taken has to be known up front
+    let mut cursor = namespace.walk();
+    for def in namespace.children_by_field_name("body", &mut cursor) {
+        if let Some(name) = def.child_by_field_name("name")
+            && def.kind() != "alias"
+            && def.kind() != "errors"
+            && !is_generic(&def)
+        {
+            c.names.push(c.text(&name).to_string());
+        }
+    }
+
+    let mut cursor = namespace.walk();
+    for def in namespace.children_by_field_name("body", &mut cursor) {
+        if matches!(def.kind(), "comment" | "alias" | "errors") {
+            continue;
+        }
+        if is_generic(&def) {
+            if def.kind() != "object" {
+                c.error("only object defs can be generic", &def);
+            }
+            continue;
+        }
+        let Some(name) = def.child_by_field_name("name") else {
+            c.error("definition is missing a name", &def);
+            continue;
+        };
+        let name = c.text(&name).to_string();
+        // lowering it would hoist its objects and spread into the def that's already there
+        if res.defs.contains_key(&name) {
+            c.error(format!("`{name}` is already defined"), &def);
+            continue;
+        }
+
+        let lowered = match def.kind() {
+            "object" => c.body(&def, &name).map(AtpTypes::Object),
+            "record" => {
+                let key = c.record_key(&def);
+                c.body(&def, &name).map(|record| {
+                    AtpTypes::Record(AtpRecord {
+                        description: None,
+                        key,
+                        record,
+                    })
+                })
+            }
+            "token" => Some(AtpTypes::Token(AtpToken::new())),
+            "query" => Some(AtpTypes::Query(c.query(&def, &name))),
+            "procedure" => Some(AtpTypes::Procedure(c.procedure(&def, &name))),
+            kind => {
+                c.error(format!("`{kind}` definitions are not supported yet"), &def);
+                None
+            }
+        };
+
+        if let Some(lowered) = lowered {
+            res.defs.insert(name, lowered);
+        }
+        // objects written inline go right after the def they came from
+        for (name, hoisted) in std::mem::take(&mut c.hoisted) {
+            res.defs.insert(name, hoisted);
+        }
+    }
+
+    // local tokens can be used before they're declared, so they're only checked once every def is in
+    for (name, range) in std::mem::take(&mut c.tokens) {
+        let message = match res.defs.get(&name) {
+            Some(AtpTypes::Token(_)) => continue,
+            Some(other) => format!("`#{name}` is a {}, not a token", other.kind()),
+            None => format!("token `#{name}` isn't defined"),
+        };
+        c.diagnostics.push(Diagnostic::error(message, range));
+    }
+
+    let spreads = std::mem::take(&mut c.spreads);
+    c.diagnostics
+        .extend(spread::apply(&mut res, c.catalog, &spreads));
+
+    // problems inside an alias come up again at every use, they're only reported once
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    for diagnostic in c.diagnostics {
+        if !diagnostics.contains(&diagnostic) {
+            diagnostics.push(diagnostic);
+        }
+    }
+    (res, diagnostics)
+}
+
+struct Compiler<'a> {
+    src: &'a str,
+    catalog: &'a Catalog,
+    nsid: String,
+    // local tokens referenced from known values, checked after all defs are lowered
+    tokens: Vec<(String, Range)>,
+    aliases: Aliases<'a>,
+    // def names, declared and hoisted
+    names: Vec<String>,
+    // inline objects lifted out of the def being lowered
+    hoisted: Vec<(String, AtpTypes)>,
+    // generic defs that have been filled in, each one is only written out once
+    // by name, with the arguments it was filled in with
+    instances: IndexMap<String, String>,
+    // `errors Name { ... }`, like aliases they never show up in the lexicon
+    error_sets: ErrorSets<'a>,
+    // applied once every def is lowered, they can point at defs further down
+    spreads: Vec<Spread>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Compiler<'a> {
+    fn text(&self, node: &Node) -> &str {
+        &self.src[node.byte_range()]
+    }
+
+    fn error(&mut self, message: impl Into<String>, node: &Node) {
+        self.diagnostics
+            .push(Diagnostic::error(message, node.range()));
+    }
+
+    // `record(nsid) foo { ... }`, records without a key use tids
+    fn record_key(&mut self, def: &Node) -> RecordKey {
+        let Some(key) = def.child_by_field_name("key") else {
+            return RecordKey::Tid;
+        };
+        let text = match key.kind() {
+            "string" => ir::literal::string(self.text(&key)),
+            _ => Ok(self.text(&key).to_string()),
+        };
+        match text.and_then(|x| x.parse()) {
+            Ok(key) => key,
+            Err(e) => {
+                self.error(e, &key);
+                RecordKey::Tid
+            }
+        }
+    }
+
+    // `type Tag = String(len=..640);` and generic defs, collected up front so they can be used early
+    // each alias is also lowered once on its own, so mistakes in unused ones still show up
+    fn aliases(&mut self, namespace: &Node<'a>) {
+        let mut cursor = namespace.walk();
+        for def in namespace.children_by_field_name("body", &mut cursor) {
+            let inserted = match def.kind() {
+                "alias" => Alias::from(self.src, &def).and_then(|x| self.aliases.insert(x)),
+                "object" if is_generic(&def) => {
+                    Template::from(self.src, &def).and_then(|x| self.aliases.insert_template(x))
+                }
+                _ => continue,
+            };
+            if let Err(e) = inserted {
+                self.error(e, &def);
+            }
+        }
+        let targets: Vec<_> = self.aliases.iter().map(|x| x.target.clone()).collect();
+        for target in targets {
+            if let Target::Type(target) = target {
+                let kind = PropKind::from(self.aliases.expand(target));
+                self.diagnostics.extend(kind.check());
+            }
+        }
+    }
+
+    fn body(&mut self, def: &Node, name: &str) -> Option<AtpObject> {
+        let Some(body) = def.child_by_field_name("body") else {
+            self.error("definition is missing a body", def);
+            return None;
+        };
+        Some(self.object(&body, name))
+    }
+
+    // a def body, or the params, input or output of a query or procedure, parsed the same way as
+    // inline objects, its spreads go into `name` and objects written inline in it are hoisted under it
+    fn object(&mut self, body: &Node, name: &str) -> AtpObject {
+        let object = object::Type::parse(self.src, body, &self.aliases);
+        self.diagnostics.extend(object.check());
+        self.lower_object(object, name)
+    }
+
+    // `paginated query getTimeline { params { ... } output { ... } errors { ... } }`
+    // inline objects in either one are hoisted as `getTimelineParams...` and `getTimelineOutput...`
+    fn query(&mut self, def: &Node, name: &str) -> AtpQuery {
+        let mut query = AtpQuery::new();
+        query.parameters = self.params(def, name);
+        query.output = self.body_io(def, "output", name);
+        query.errors = self.errors(def);
+
+        let modifier = def.child_by_field_name("modifier");
+        match modifier.map(|x| GenericProp::from(self.src, &x)) {
+            Some(Ok(modifier)) => {
+                let (bounds, diagnostics) = Bounds::parse(modifier);
+                self.diagnostics.extend(diagnostics);
+                for e in pagination::paginate(&mut query, bounds) {
+                    self.error(e, def);
+                }
+            }
+            Some(Err(())) => self.error("expected a modifier, e.g. `paginated`", def),
+            None => {
+                for problem in pagination::lint(&query) {
+                    let message = format!("{problem}, `paginated query` adds them the usual way");
+                    self.diagnostics
+                        .push(Diagnostic::warning(message, def.range()));
+                }
+            }
+        }
+        query
+    }
+
+    // `procedure createPost { params { ... } input { ... } output { ... } errors { ... } }`
+    fn procedure(&mut self, def: &Node, name: &str) -> AtpProcedure {
+        if let Some(modifier) = def.child_by_field_name("modifier") {
+            self.error("only queries can be `paginated`", &modifier);
+        }
+        let mut procedure = AtpProcedure::new();
+        procedure.parameters = self.params(def, name);
+        procedure.input = self.body_io(def, "input", name);
+        procedure.output = self.body_io(def, "output", name);
+        procedure.errors = self.errors(def);
+        procedure
+    }
+
+    // `input` or `output` of a query or procedure, always json
+    fn body_io(&mut self, def: &Node, field: &str, name: &str) -> Option<RpcIO> {
+        let body = def.child_by_field_name(field)?;
+        let start = self.spreads.len();
+        let hoisted = hoisted_name(name, field);
+        let object = self.object(&body, &hoisted);
+        // the body stays inside the query or procedure, there's no def called `hoisted` to spread into
+        for spread in &mut self.spreads[start..] {
+            if spread.into == hoisted {
+                spread.into = name.to_string();
+                spread.section = Some(field.to_string());
+            }
+        }
+        Some(RpcIO {
+            description: None,
+            encoding: "application/json".to_string(),
+            schema: Some(RpcSchema::Object(object)),
+        })
+    }
+
+    // `errors { InvalidCursor; ...RepoErrors; }`, with the sets written out
+    fn errors(&mut self, def: &Node) -> Option<Vec<RpcError>> {
+        let body = def.child_by_field_name("errors")?;
+        let (errors, diagnostics) = self.error_sets.expand(self.src, &body);
+        self.diagnostics.extend(diagnostics);
+        Some(errors)
+    }
+
+    // `errors RepoErrors { ... }`, collected up front so they can be included before they're declared
+    fn error_sets(&mut self, namespace: &Node<'a>) {
+        let mut cursor = namespace.walk();
+        for def in namespace.children_by_field_name("body", &mut cursor) {
+            if def.kind() != "errors" {
+                continue;
+            }
+            let (Some(name), Some(body)) = (
+                def.child_by_field_name("name"),
+                def.child_by_field_name("body"),
+            ) else {
+                self.error("error sets are written `errors Name { ErrorName; }`", &def);
+                continue;
+            };
+            let name = self.text(&name).to_string();
+            if let Err(e) = self.error_sets.insert(name, body) {
+                self.error(e, &def);
+            }
+        }
+        let diagnostics = self.error_sets.check(self.src);
+        self.diagnostics.extend(diagnostics);
+    }
+
+    // query params can only be booleans, integers, strings, unknown and arrays of those
+    fn params(&mut self, def: &Node, name: &str) -> Option<AtpParams> {
+        let body = def.child_by_field_name("params")?;
+        let start = self.spreads.len();
+        let hoisted = hoisted_name(name, "params");
+        let object = object::Type::parse(self.src, &body, &self.aliases);
+        self.diagnostics.extend(object.check());
+        let locs: IndexMap<String, Range> = object
+            .props
+            .iter()
+            .map(|(name, prop)| (name.clone(), prop.loc))
+            .collect();
+        let object = self.lower_object(object, &hoisted);
+        for spread in self.spreads.split_off(start) {
+            if spread.into == hoisted {
+                self.diagnostics.push(Diagnostic::error(
+                    "params can't use spreads, write them out",
+                    spread.loc,
+                ));
+            } else {
+                self.spreads.push(spread);
+            }
+        }
+        let mut params = AtpParams::new();
+        params.required = object.required;
+        for (prop, t) in object.properties {
+            match param_props(t) {
+                Some(t) => {
+                    params.properties.insert(prop, t);
+                }
+                None => {
+                    let message = format!(
+                        "`{prop}` can't be a query param, only booleans, integers, strings, unknown and arrays of them"
+                    );
+                    self.diagnostics
+                        .push(Diagnostic::error(message, locs[&prop]));
+                }
+            }
+        }
+        Some(params)
+    }
+
+    fn check_ref(&mut self, reference: &str, loc: Range) {
+        let nsid = reference.split('#').next().unwrap_or(reference);
+        if self.catalog.contains(nsid) && self.catalog.resolve(reference).is_none() {
+            self.diagnostics.push(Diagnostic::error(
+                format!("`{reference}` doesn't exist in {nsid}"),
+                loc,
+            ));
+        }
+    }
+
+    // `known=[#foo, "bar"]`, token refs become `nsid#foo` strings
+    fn known_values(&mut self, values: Vec<KnownValue>, loc: Range) -> Vec<String> {
+        let mut out = Vec::new();
+        for value in values {
+            match value {
+                KnownValue::Literal(s) => out.push(s),
+                KnownValue::Token(reference) => match reference.strip_prefix('#') {
+                    Some(name) => {
+                        self.tokens.push((name.to_string(), loc));
+                        out.push(format!("{}#{name}", self.nsid));
+                    }
+                    None => {
+                        self.check_token(&reference, loc);
+                        out.push(reference);
+                    }
+                },
+            }
+        }
+        out
+    }
+
+    fn check_token(&mut self, reference: &str, loc: Range) {
+        let nsid = reference.split('#').next().unwrap_or(reference);
+        if !self.catalog.contains(nsid) {
+            return;
+        }
+        let message = match self.catalog.resolve(reference) {
+            Some(AtpTypes::Token(_)) => return,
+            Some(other) => format!("`{reference}` is a {}, not a token", other.kind()),
+            None => format!("`{reference}` doesn't exist in {nsid}"),
+        };
+        self.diagnostics.push(Diagnostic::error(message, loc));
+    }
+
+    // `at://did:plc:abc/com.example.profile/self`, the key has to fit the record def of its collection
+    fn check_at_uri(&mut self, uri: &str, loc: Range) {
+        let Some(path) = uri.strip_prefix("at://") else {
+            return;
+        };
+        let path = path.split(['?', '#']).next().unwrap_or(path);
+        let mut segments = path.split('/').skip(1);
+        let (Some(collection), Some(rkey)) = (segments.next(), segments.next()) else {
+            return;
+        };
+        if !self.catalog.contains(collection) {
+            return;
+        }
+        if let Err(e) = self.catalog.check_record_key(collection, rkey) {
+            self.diagnostics
+                .push(Diagnostic::error(format!("`{uri}`: {e}"), loc));
+        }
+    }
+
+    // the IR lowers itself, this only adds what needs the rest of the file or the catalog
+    fn lower(&mut self, kind: PropKind, name: &str) -> AtpTypes {
+        match kind {
+            PropKind::String(mut string) => {
+                let known = string
+                    .known_values
+                    .take()
+                    .map(|x| self.known_values(x, string.loc));
+                let loc = string.loc;
+                let mut atp_string: AtpString = string.into();
+                atp_string.known_values = known;
+                if atp_string.format == Some(StringFormats::AtUri) {
+                    for uri in [&atp_string.default, &atp_string.constant]
+                        .into_iter()
+                        .flatten()
+                    {
+                        self.check_at_uri(uri, loc);
+                    }
+                }
+                AtpTypes::String(atp_string)
+            }
+            PropKind::Ref(reference) => {
+                self.check_ref(&reference.reference, reference.loc);
+                reference.into()
+            }
+            PropKind::Union(union) => {
+                for reference in &union.refs {
+                    self.check_ref(reference, union.loc);
+                }
+                union.into()
+            }
+            PropKind::Array(array) => AtpTypes::Array(AtpArray {
+                description: array.description,
+                min_length: array.length.start.and_then(|x| x.try_into().ok()),
+                max_length: array.length.end.and_then(|x| x.try_into().ok()),
+                items: Box::new(self.lower(*array.items, name)),
+            }),
+            PropKind::Object(object) => {
+                // an inline `{ ... }` becomes a def of its own, a bare `Object` stays where it is
+                let inline = !object.props.is_empty() || !object.spreads.is_empty();
+                self.hoist(object, name, inline)
+            }
+            PropKind::Generic(instance) => {
+                // every use of the same arguments points at the one def
+                match self.instances.get(&instance.name) {
+                    None => {
+                        self.instances.insert(instance.name.clone(), instance.key);
+                        self.hoist(instance.object, &instance.name, true);
+                    }
+                    Some(key) if *key != instance.key => {
+                        let message = format!(
+                            "`{}` is already used for `{key}`, give one of them a def of its own",
+                            instance.name
+                        );
+                        self.diagnostics
+                            .push(Diagnostic::error(message, instance.object.loc));
+                    }
+                    Some(_) => {}
+                }
+                AtpTypes::Ref(AtpRef {
+                    description: None,
+                    reference: format!("#{}", instance.name),
+                })
+            }
+            other => other.into(),
+        }
+    }
+
+    // lowers an object into a def called `name` and returns a ref to it, or the object itself
+    // when it stays where it is or the name is taken
+    fn hoist(&mut self, mut object: object::Type, name: &str, inline: bool) -> AtpTypes {
+        let hoisted = inline && self.claim(name, object.loc);
+        if !hoisted {
+            // there's no def of its own to spread into
+            for (reference, _, loc) in std::mem::take(&mut object.spreads) {
+                self.diagnostics.push(Diagnostic::error(
+                    format!("`{reference}` isn't spread, the object it's in has no def of its own"),
+                    loc,
+                ));
+            }
+            return AtpTypes::Object(self.lower_object(object, name));
+        }
+
+        // the slot keeps the def ahead of the objects hoisted out of it
+        let slot = self.hoisted.len();
+        self.hoisted
+            .push((name.to_string(), AtpTypes::Unknown(AtpUnknown::new())));
+        self.hoisted[slot].1 = AtpTypes::Object(self.lower_object(object, name));
+        AtpTypes::Ref(AtpRef {
+            description: None,
+            reference: format!("#{name}"),
+        })
+    }
+
+    // the props in order, objects written inline in them are hoisted under `name`
+    // and spreads are applied to the def called `name` once every def is in
+    fn lower_object(&mut self, object: object::Type, name: &str) -> AtpObject {
+        let mut atp_object = AtpObject::new();
+        atp_object.description = object.description;
+        let mut required = Vec::new();
+        for (prop_name, prop) in object.props {
+            if !prop.optional {
+                required.push(prop_name.clone());
+            }
+            let value = self.lower(prop.value, &hoisted_name(name, &prop_name));
+            atp_object.properties.insert(prop_name, value);
+        }
+        if !required.is_empty() {
+            atp_object.required = Some(required);
+        }
+        for (reference, position, loc) in object.spreads {
+            self.spreads.push(Spread {
+                into: name.to_string(),
+                section: None,
+                reference,
+                position,
+                loc,
+            });
+        }
+        atp_object
+    }
+
+    // takes a def name for a hoisted object, a name that's already in use is an error
+    fn claim(&mut self, name: &str, loc: Range) -> bool {
+        if self.names.iter().any(|x| x == name) {
+            self.diagnostics.push(Diagnostic::error(
+                format!(
+                    "`{name}` is already defined, move this object into a def with another name"
+                ),
+                loc,
+            ));
+            return false;
+        }
+        self.names.push(name.to_string());
+        true
+    }
+}
+
+fn param_props(t: AtpTypes) -> Option<ParamProps> {
+    match t {
+        AtpTypes::Boolean(x) => Some(ParamProps::Boolean(x)),
+        AtpTypes::Integer(x) => Some(ParamProps::Integer(x)),
+        AtpTypes::String(x) => Some(ParamProps::String(x)),
+        AtpTypes::Unknown(x) => Some(ParamProps::Unknown(x)),
+        AtpTypes::Array(x) => Some(ParamProps::Array {
+            description: x.description,
+            items: Box::new(
+                param_props(*x.items).filter(|x| !matches!(x, ParamProps::Array { .. }))?,
+            ),
+            min_length: x.min_length,
+            max_length: x.max_length,
+        }),
+        _ => None,
+    }
+}
+
+// `Page<T> { ... }`, only written out where it's used
+fn is_generic(def: &Node) -> bool {
+    def.child_by_field_name("generic").is_some()
+}
+
+// the def an inline object is hoisted into, e.g. `image` in `main` becomes `mainImage`
+fn hoisted_name(parent: &str, prop: &str) -> String {
+    let mut name = parent.to_string();
+    for part in prop.split(['_', '-']) {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            name.extend(first.to_uppercase());
+            name.push_str(chars.as_str());
+        }
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    fn compile_src(src: &str) -> (Lexicon, Vec<String>) {
+        compile_with(src, &Catalog::new())
+    }
+
+    fn compile_with(src: &str, catalog: &Catalog) -> (Lexicon, Vec<String>) {
+        let tree = parser().parse(src, None).unwrap();
+        let (lexicon, diagnostics) = compile(src, &tree, catalog);
+        let messages = diagnostics.into_iter().map(|x| x.message).collect();
+        (lexicon, messages)
+    }
+
+    #[test]
+    fn hoisted_names() {
+        assert_eq!(hoisted_name("main", "image"), "mainImage");
+        assert_eq!(
+            hoisted_name("mainImage", "aspect_ratio"),
+            "mainImageAspectRatio"
+        );
+        assert_eq!(hoisted_name("post", "reply-ref"), "postReplyRef");
+    }
+
+    #[test]
+    fn instances() {
+        let (lexicon, messages) = compile_src(
+            r#"@com.example.test {
+                Page<T> { items: T[..100] }
+                type Post = #post;
+                post { a: String }
+                feed {
+                    a: Page<#post>;
+                    b: Page<Post>;
+                    c: Page<String(len=..10)>;
+                    d: Page<String(len=10..)>;
+                }
+            }"#,
+        );
+        assert!(messages.is_empty(), "{messages:?}");
+        let names: Vec<&str> = lexicon.defs.keys().map(|x| x.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "post",
+                "feed",
+                "pagePost",
+                "pageStringLenTo10",
+                "pageStringLen10To"
+            ]
+        );
+    }
+
+    #[test]
+    fn at_uri_record_keys() {
+        let mut catalog = Catalog::new();
+        catalog
+            .load_str(
+                std::path::Path::new("profile.json"),
+                r#"{ "lexicon": 1, "id": "com.example.profile", "defs": { "main": {
+                    "type": "record", "key": "literal:self", "record": { "type": "object", "properties": {} }
+                } } }"#,
+            )
+            .unwrap();
+        let (_, messages) = compile_with(
+            r#"@com.example.test {
+                main {
+                    a: String(format="at-uri", const="at://did:plc:abc/com.example.profile/self");
+                    b: String(format="at-uri", default="at://did:plc:abc/com.example.profile/other");
+                    c: String(format="at-uri", default="at://did:plc:abc/com.example.unknown/other");
+                }
+            }"#,
+            &catalog,
+        );
+        assert_eq!(
+            messages,
+            ["`at://did:plc:abc/com.example.profile/other`: record key must be `self`"]
+        );
+    }
+
+    #[test]
+    fn rpc_spreads() {
+        let (lexicon, messages) = compile_src(
+            r#"@com.example.test {
+                common { createdAt: String; }
+                query getFeed {
+                    params { ...#common; actor: String; }
+                    output { ...#common; feed: String; }
+                }
+                procedure createPost {
+                    input { text: String; ...#common; }
+                }
+            }"#,
+        );
+        assert_eq!(messages, ["params can't use spreads, write them out"]);
+        let AtpTypes::Query(query) = &lexicon.defs["getFeed"] else {
+            panic!("getFeed should be a query");
+        };
+        let Some(RpcSchema::Object(output)) = &query.output.as_ref().unwrap().schema else {
+            panic!("the output should be an object");
+        };
+        let names: Vec<&str> = output.properties.keys().map(|x| x.as_str()).collect();
+        assert_eq!(names, ["createdAt", "feed"]);
+        let AtpTypes::Procedure(procedure) = &lexicon.defs["createPost"] else {
+            panic!("createPost should be a procedure");
+        };
+        let Some(RpcSchema::Object(input)) = &procedure.input.as_ref().unwrap().schema else {
+            panic!("the input should be an object");
+        };
+        let names: Vec<&str> = input.properties.keys().map(|x| x.as_str()).collect();
+        assert_eq!(names, ["text", "createdAt"]);
+    }
+
+    #[test]
+    fn param_types() {
+        let src = "@com.example.test {\n    query getFeed {\n        params { actor: String; filter: { a: String; } }\n    }\n}";
+        let tree = parser().parse(src, None).unwrap();
+        let (_, diagnostics) = compile(src, &tree, &Catalog::new());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "`filter` can't be a query param, only booleans, integers, strings, unknown and arrays of them"
+        );
+        assert_eq!(
+            &src[diagnostics[0].loc.start_byte..diagnostics[0].loc.end_byte],
+            "filter: { a: String; }"
+        );
+    }
+
+    #[test]
+    fn unhoisted_spreads() {
+        let (_, messages) = compile_src(
+            r#"@com.example.test {
+                common { a: String; }
+                mainImage { b: String; }
+                main { image: { ...#common; c: String; } }
+            }"#,
+        );
+        assert_eq!(
+            messages,
+            [
+                "`mainImage` is already defined, move this object into a def with another name",
+                "`#common` isn't spread, the object it's in has no def of its own",
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicate_defs() {
+        let (lexicon, messages) = compile_src(
+            r#"@com.example.test {
+                common { a: String; }
+                main { b: String; }
+                main { ...#common; image: { c: String; } }
+            }"#,
+        );
+        assert_eq!(messages, ["`main` is already defined"]);
+        let names: Vec<&str> = lexicon.defs.keys().map(|x| x.as_str()).collect();
+        assert_eq!(names, ["common", "main"]);
+        let AtpTypes::Object(main) = &lexicon.defs["main"] else {
+            panic!("main should be an object");
+        };
+        let names: Vec<&str> = main.properties.keys().map(|x| x.as_str()).collect();
+        assert_eq!(names, ["b"]);
+    }
+}
@@ -0,0 +1,611 @@
+use std::{fmt::Display, hash::Hash};
+
+use lexicon::{
+    AtpArray, AtpBlob, AtpBoolean, AtpBytes, AtpInteger, AtpObject, AtpParams, AtpString, AtpTypes,
+    AtpUnion, IndexMap, Lexicon, ParamProps, RpcError, RpcIO, RpcSchema,
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum Compat {
+    Compatible,
+    Breaking,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Change {
+    pub compat: Compat,
+    // where the change is, e.g. `main.record.tags[]`
+    pub path: String,
+    pub message: String,
+}
+
+// everything that differs between two revisions of a lexicon, classified by whether
+// existing records and clients keep working with the new one
+pub fn diff(old: &Lexicon, new: &Lexicon) -> Vec<Change> {
+    let mut d = Differ {
+        changes: Vec::new(),
+    };
+    if old.id != new.id {
+        d.breaking("", format!("nsid changed from {} to {}", old.id, new.id));
+    }
+    if old.description != new.description {
+        d.compatible("", "description changed");
+    }
+    d.map(
+        "",
+        &old.defs,
+        &new.defs,
+        |d, path, _| d.compatible(path, "definition added"),
+        |d, path, _| d.breaking(path, "definition removed"),
+        |d, path, a, b| d.types(path, a, b),
+    );
+    d.changes
+}
+
+pub fn is_breaking(changes: &[Change]) -> bool {
+    changes.iter().any(|x| x.compat == Compat::Breaking)
+}
+
+// the revision the new lexicon should be published with
+pub fn suggest_revision(old: &Lexicon, changes: &[Change]) -> Option<i32> {
+    match (old.revision, changes.is_empty()) {
+        (revision, true) => revision,
+        (Some(revision), false) => Some(revision + 1),
+        (None, false) => Some(1),
+    }
+}
+
+pub fn report(old: &Lexicon, changes: &[Change]) -> String {
+    let mut out = String::new();
+    for change in changes {
+        let marker = match change.compat {
+            Compat::Breaking => "breaking",
+            Compat::Compatible => "compatible",
+        };
+        let path = if change.path.is_empty() {
+            old.id.clone()
+        } else {
+            format!("{}#{}", old.id, change.path)
+        };
+        out.push_str(&format!("{marker:>10}  {path}: {}\n", change.message));
+    }
+
+    if changes.is_empty() {
+        out.push_str("no changes\n");
+        return out;
+    }
+    let revision = suggest_revision(old, changes).unwrap_or_default();
+    match old.revision {
+        Some(old) => out.push_str(&format!("\nbump revision from {old} to {revision}\n")),
+        None => out.push_str(&format!("\nset revision to {revision}\n")),
+    }
+    if is_breaking(changes) {
+        out.push_str(
+            "this revision breaks existing records or clients, consider publishing it under a new nsid\n",
+        );
+    }
+    out
+}
+
+struct Differ {
+    changes: Vec<Change>,
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{path}.{name}")
+    }
+}
+
+impl Differ {
+    fn breaking(&mut self, path: &str, message: impl Into<String>) {
+        self.changes.push(Change {
+            compat: Compat::Breaking,
+            path: path.to_string(),
+            message: message.into(),
+        });
+    }
+
+    fn compatible(&mut self, path: &str, message: impl Into<String>) {
+        self.changes.push(Change {
+            compat: Compat::Compatible,
+            path: path.to_string(),
+            message: message.into(),
+        });
+    }
+
+    // walks two maps in declaration order, old keys first, calling back for keys that were added, removed or kept
+    fn map<K: Hash + Eq + Display, V>(
+        &mut self,
+        path: &str,
+        old: &IndexMap<K, V>,
+        new: &IndexMap<K, V>,
+        added: impl Fn(&mut Self, &str, &V),
+        removed: impl Fn(&mut Self, &str, &V),
+        kept: impl Fn(&mut Self, &str, &V, &V),
+    ) {
+        let added_keys = new.keys().filter(|x| !old.contains_key(*x));
+        for key in old.keys().chain(added_keys) {
+            let path = join(path, &key.to_string());
+            match (old.get(key), new.get(key)) {
+                (None, Some(b)) => added(self, &path, b),
+                (Some(a), None) => removed(self, &path, a),
+                (Some(a), Some(b)) => kept(self, &path, a, b),
+                (None, None) => {}
+            }
+        }
+    }
+
+    fn description(&mut self, path: &str, old: &Option<String>, new: &Option<String>) {
+        if old != new {
+            self.compatible(path, "description changed");
+        }
+    }
+
+    // an upper limit, lowering or introducing it breaks values that used to be valid
+    fn max<T: PartialOrd + Display>(
+        &mut self,
+        path: &str,
+        name: &str,
+        old: Option<T>,
+        new: Option<T>,
+    ) {
+        match (old, new) {
+            (None, Some(b)) => self.breaking(path, format!("{name} added ({b})")),
+            (Some(_), None) => self.compatible(path, format!("{name} removed")),
+            (Some(a), Some(b)) if b < a => {
+                self.breaking(path, format!("{name} tightened from {a} to {b}"))
+            }
+            (Some(a), Some(b)) if b > a => {
+                self.compatible(path, format!("{name} widened from {a} to {b}"))
+            }
+            _ => {}
+        }
+    }
+
+    // a lower limit, raising or introducing it breaks values that used to be valid
+    fn min<T: PartialOrd + Display>(
+        &mut self,
+        path: &str,
+        name: &str,
+        old: Option<T>,
+        new: Option<T>,
+    ) {
+        match (old, new) {
+            (None, Some(b)) => self.breaking(path, format!("{name} added ({b})")),
+            (Some(_), None) => self.compatible(path, format!("{name} removed")),
+            (Some(a), Some(b)) if b > a => {
+                self.breaking(path, format!("{name} tightened from {a} to {b}"))
+            }
+            (Some(a), Some(b)) if b < a => {
+                self.compatible(path, format!("{name} widened from {a} to {b}"))
+            }
+            _ => {}
+        }
+    }
+
+    fn constant<T: PartialEq + std::fmt::Debug>(
+        &mut self,
+        path: &str,
+        old: &Option<T>,
+        new: &Option<T>,
+    ) {
+        if old != new {
+            self.breaking(path, format!("const changed from {old:?} to {new:?}"));
+        }
+    }
+
+    fn default<T: PartialEq + std::fmt::Debug>(
+        &mut self,
+        path: &str,
+        old: &Option<T>,
+        new: &Option<T>,
+    ) {
+        if old != new {
+            self.compatible(path, format!("default changed from {old:?} to {new:?}"));
+        }
+    }
+
+    // closed sets of values, dropping a value or closing an open set breaks existing values
+    fn enumeration<T: PartialEq + std::fmt::Debug>(
+        &mut self,
+        path: &str,
+        old: &Option<Vec<T>>,
+        new: &Option<Vec<T>>,
+    ) {
+        match (old, new) {
+            (None, Some(_)) => self.breaking(path, "enum added"),
+            (Some(_), None) => self.compatible(path, "enum removed"),
+            (Some(a), Some(b)) => {
+                for value in a.iter().filter(|x| !b.contains(x)) {
+                    self.breaking(path, format!("enum value {value:?} removed"));
+                }
+                for value in b.iter().filter(|x| !a.contains(x)) {
+                    self.compatible(path, format!("enum value {value:?} added"));
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    fn types(&mut self, path: &str, old: &AtpTypes, new: &AtpTypes) {
+        if old.kind() != new.kind() {
+            return self.breaking(
+                path,
+                format!("type changed from {} to {}", old.kind(), new.kind()),
+            );
+        }
+        self.description(
+            path,
+            &old.description().cloned(),
+            &new.description().cloned(),
+        );
+
+        match (old, new) {
+            (AtpTypes::Boolean(a), AtpTypes::Boolean(b)) => self.boolean(path, a, b),
+            (AtpTypes::Integer(a), AtpTypes::Integer(b)) => self.integer(path, a, b),
+            (AtpTypes::String(a), AtpTypes::String(b)) => self.string(path, a, b),
+            (AtpTypes::Bytes(a), AtpTypes::Bytes(b)) => self.bytes(path, a, b),
+            (AtpTypes::Blob(a), AtpTypes::Blob(b)) => self.blob(path, a, b),
+            (AtpTypes::Array(a), AtpTypes::Array(b)) => self.array(path, a, b),
+            (AtpTypes::Object(a), AtpTypes::Object(b)) => self.object(path, a, b),
+            (AtpTypes::Params(a), AtpTypes::Params(b)) => self.params(path, a, b),
+            (AtpTypes::Ref(a), AtpTypes::Ref(b)) if a.reference != b.reference => self.breaking(
+                path,
+                format!("ref changed from {} to {}", a.reference, b.reference),
+            ),
+            (AtpTypes::Union(a), AtpTypes::Union(b)) => self.union(path, a, b),
+            (AtpTypes::Record(a), AtpTypes::Record(b)) => {
+                if a.key != b.key {
+                    self.breaking(
+                        path,
+                        format!("record key changed from {} to {}", a.key, b.key),
+                    );
+                }
+                self.object(&join(path, "record"), &a.record, &b.record);
+            }
+            (AtpTypes::Query(a), AtpTypes::Query(b)) => {
+                self.optional_params(path, &a.parameters, &b.parameters);
+                self.io(&join(path, "output"), &a.output, &b.output);
+                self.errors(path, &a.errors, &b.errors);
+            }
+            (AtpTypes::Procedure(a), AtpTypes::Procedure(b)) => {
+                self.optional_params(path, &a.parameters, &b.parameters);
+                self.io(&join(path, "input"), &a.input, &b.input);
+                self.io(&join(path, "output"), &a.output, &b.output);
+                self.errors(path, &a.errors, &b.errors);
+            }
+            (AtpTypes::Subscription(a), AtpTypes::Subscription(b)) => {
+                self.optional_params(path, &a.parameters, &b.parameters);
+                self.union(&join(path, "message"), &a.message.schema, &b.message.schema);
+                self.errors(path, &a.errors, &b.errors);
+            }
+            _ => {}
+        }
+    }
+
+    fn boolean(&mut self, path: &str, a: &AtpBoolean, b: &AtpBoolean) {
+        self.constant(path, &a.constant, &b.constant);
+        self.default(path, &a.default, &b.default);
+    }
+
+    fn integer(&mut self, path: &str, a: &AtpInteger, b: &AtpInteger) {
+        self.min(path, "minimum", a.minimum, b.minimum);
+        self.max(path, "maximum", a.maximum, b.maximum);
+        self.enumeration(path, &a.enumeration, &b.enumeration);
+        self.constant(path, &a.constant, &b.constant);
+        self.default(path, &a.default, &b.default);
+    }
+
+    fn string(&mut self, path: &str, a: &AtpString, b: &AtpString) {
+        match (&a.format, &b.format) {
+            (None, Some(f)) => self.breaking(path, format!("format {f} added")),
+            (Some(f), None) => self.compatible(path, format!("format {f} removed")),
+            (Some(x), Some(y)) if x != y => {
+                self.breaking(path, format!("format changed from {x} to {y}"))
+            }
+            _ => {}
+        }
+        self.min(path, "minLength", a.min_length, b.min_length);
+        self.max(path, "maxLength", a.max_length, b.max_length);
+        self.min(path, "minGraphemes", a.min_graphemes, b.min_graphemes);
+        self.max(path, "maxGraphemes", a.max_graphemes, b.max_graphemes);
+        if a.known_values != b.known_values {
+            self.compatible(path, "knownValues changed");
+        }
+        self.enumeration(path, &a.enumeration, &b.enumeration);
+        self.constant(path, &a.constant, &b.constant);
+        self.default(path, &a.default, &b.default);
+    }
+
+    fn bytes(&mut self, path: &str, a: &AtpBytes, b: &AtpBytes) {
+        self.min(path, "minLength", a.min_length, b.min_length);
+        self.max(path, "maxLength", a.max_length, b.max_length);
+    }
+
+    fn blob(&mut self, path: &str, a: &AtpBlob, b: &AtpBlob) {
+        match (&a.accept, &b.accept) {
+            (None, Some(_)) => self.breaking(path, "accept added"),
+            (Some(_), None) => self.compatible(path, "accept removed"),
+            (Some(x), Some(y)) => {
+                for mime in x.iter().filter(|m| !y.contains(m)) {
+                    self.breaking(path, format!("no longer accepts {mime}"));
+                }
+                for mime in y.iter().filter(|m| !x.contains(m)) {
+                    self.compatible(path, format!("now accepts {mime}"));
+                }
+            }
+            (None, None) => {}
+        }
+        self.max(path, "maxSize", a.max_size, b.max_size);
+    }
+
+    fn array(&mut self, path: &str, a: &AtpArray, b: &AtpArray) {
+        self.min(path, "minLength", a.min_length, b.min_length);
+        self.max(path, "maxLength", a.max_length, b.max_length);
+        self.types(&format!("{path}[]"), &a.items, &b.items);
+    }
+
+    fn union(&mut self, path: &str, a: &AtpUnion, b: &AtpUnion) {
+        let closed = a.closed.unwrap_or(false);
+        if closed != b.closed.unwrap_or(false) {
+            let state = if closed { "opened" } else { "closed" };
+            self.breaking(path, format!("union {state}"));
+        }
+        for r in a.refs.iter().filter(|x| !b.refs.contains(x)) {
+            // open unions have to cope with types they don't know about anyway
+            if closed {
+                self.breaking(path, format!("{r} removed from closed union"));
+            } else {
+                self.compatible(path, format!("{r} removed from open union"));
+            }
+        }
+        for r in b.refs.iter().filter(|x| !a.refs.contains(x)) {
+            if closed {
+                self.breaking(path, format!("{r} added to closed union"));
+            } else {
+                self.compatible(path, format!("{r} added to open union"));
+            }
+        }
+    }
+
+    fn object(&mut self, path: &str, a: &AtpObject, b: &AtpObject) {
+        let a_required = a.required.clone().unwrap_or_default();
+        let b_required = b.required.clone().unwrap_or_default();
+        let a_nullable = a.nullable.clone().unwrap_or_default();
+        let b_nullable = b.nullable.clone().unwrap_or_default();
+
+        self.map(
+            path,
+            &a.properties,
+            &b.properties,
+            |d, path, _| {
+                let name = path.rsplit('.').next().unwrap_or(path).to_string();
+                if b_required.contains(&name) {
+                    d.breaking(path, "required property added");
+                } else {
+                    d.compatible(path, "optional property added");
+                }
+            },
+            |d, path, _| d.breaking(path, "property removed"),
+            |d, path, x, y| {
+                let name = path.rsplit('.').next().unwrap_or(path).to_string();
+                match (a_required.contains(&name), b_required.contains(&name)) {
+                    (false, true) => d.breaking(path, "property became required"),
+                    (true, false) => d.breaking(path, "property became optional"),
+                    _ => {}
+                }
+                match (a_nullable.contains(&name), b_nullable.contains(&name)) {
+                    (false, true) => d.breaking(path, "property became nullable"),
+                    (true, false) => d.breaking(path, "property is no longer nullable"),
+                    _ => {}
+                }
+                d.types(path, x, y);
+            },
+        );
+    }
+
+    fn optional_params(&mut self, path: &str, a: &Option<AtpParams>, b: &Option<AtpParams>) {
+        let empty = AtpParams::new();
+        self.params(
+            &join(path, "parameters"),
+            a.as_ref().unwrap_or(&empty),
+            b.as_ref().unwrap_or(&empty),
+        );
+    }
+
+    fn params(&mut self, path: &str, a: &AtpParams, b: &AtpParams) {
+        let a_required = a.required.clone().unwrap_or_default();
+        let b_required = b.required.clone().unwrap_or_default();
+
+        self.map(
+            path,
+            &a.properties,
+            &b.properties,
+            |d, path, _| {
+                let name = path.rsplit('.').next().unwrap_or(path).to_string();
+                if b_required.contains(&name) {
+                    d.breaking(path, "required parameter added");
+                } else {
+                    d.compatible(path, "optional parameter added");
+                }
+            },
+            |d, path, _| d.breaking(path, "parameter removed"),
+            |d, path, x, y| {
+                let name = path.rsplit('.').next().unwrap_or(path).to_string();
+                if !a_required.contains(&name) && b_required.contains(&name) {
+                    d.breaking(path, "parameter became required");
+                }
+                d.param(path, x, y);
+            },
+        );
+    }
+
+    fn param(&mut self, path: &str, a: &ParamProps, b: &ParamProps) {
+        match (a, b) {
+            (ParamProps::Boolean(x), ParamProps::Boolean(y)) => self.boolean(path, x, y),
+            (ParamProps::Integer(x), ParamProps::Integer(y)) => self.integer(path, x, y),
+            (ParamProps::String(x), ParamProps::String(y)) => self.string(path, x, y),
+            (ParamProps::Unknown(_), ParamProps::Unknown(_)) => {}
+            (
+                ParamProps::Array {
+                    items: x,
+                    min_length: x_min,
+                    max_length: x_max,
+                    ..
+                },
+                ParamProps::Array {
+                    items: y,
+                    min_length: y_min,
+                    max_length: y_max,
+                    ..
+                },
+            ) => {
+                self.min(path, "minLength", *x_min, *y_min);
+                self.max(path, "maxLength", *x_max, *y_max);
+                self.param(&format!("{path}[]"), x, y);
+            }
+            _ => self.breaking(path, "parameter type changed"),
+        }
+    }
+
+    fn io(&mut self, path: &str, a: &Option<RpcIO>, b: &Option<RpcIO>) {
+        let (a, b) = match (a, b) {
+            (None, Some(_)) => return self.breaking(path, "body added"),
+            (Some(_), None) => return self.breaking(path, "body removed"),
+            (Some(a), Some(b)) => (a, b),
+            (None, None) => return,
+        };
+        if a.encoding != b.encoding {
+            self.breaking(
+                path,
+                format!("encoding changed from {} to {}", a.encoding, b.encoding),
+            );
+        }
+        self.description(path, &a.description, &b.description);
+
+        match (&a.schema, &b.schema) {
+            (None, Some(_)) => self.breaking(path, "schema added"),
+            (Some(_), None) => self.breaking(path, "schema removed"),
+            (Some(RpcSchema::Object(x)), Some(RpcSchema::Object(y))) => {
+                self.object(&join(path, "schema"), x, y)
+            }
+            (Some(RpcSchema::Union(x)), Some(RpcSchema::Union(y))) => {
+                self.union(&join(path, "schema"), x, y)
+            }
+            (Some(x), Some(y)) if x != y => self.breaking(path, "schema changed"),
+            _ => {}
+        }
+    }
+
+    // clients have to handle unexpected errors anyway, so error lists can change freely
+    fn errors(&mut self, path: &str, a: &Option<Vec<RpcError>>, b: &Option<Vec<RpcError>>) {
+        let a = a.as_deref().unwrap_or_default();
+        let b = b.as_deref().unwrap_or_default();
+        for error in a.iter().filter(|x| !b.iter().any(|y| y.name == x.name)) {
+            self.compatible(path, format!("error {} removed", error.name));
+        }
+        for error in b.iter().filter(|x| !a.iter().any(|y| y.name == x.name)) {
+            self.compatible(path, format!("error {} added", error.name));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lexicon(defs: &str) -> Lexicon {
+        serde_json::from_str(&format!(
+            r#"{{ "lexicon": 1, "id": "com.example.test", "defs": {defs} }}"#
+        ))
+        .unwrap()
+    }
+
+    fn record(properties: &str, required: &str) -> Lexicon {
+        lexicon(&format!(
+            r#"{{ "main": {{ "type": "record", "key": "tid", "record": {{
+                "type": "object", "required": {required}, "properties": {properties}
+            }} }} }}"#
+        ))
+    }
+
+    #[test]
+    fn identical() {
+        let a = record(r#"{ "a": { "type": "string" } }"#, r#"["a"]"#);
+        let b = record(r#"{ "a": { "type": "string" } }"#, r#"["a"]"#);
+        assert!(diff(&a, &b).is_empty());
+        assert_eq!(suggest_revision(&a, &[]), None);
+    }
+
+    #[test]
+    fn required_property_added() {
+        let a = record(r#"{ "a": { "type": "string" } }"#, r#"["a"]"#);
+        let b = record(
+            r#"{ "a": { "type": "string" }, "b": { "type": "string" } }"#,
+            r#"["a", "b"]"#,
+        );
+        let changes = diff(&a, &b);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].compat, Compat::Breaking);
+        assert_eq!(changes[0].path, "main.record.b");
+    }
+
+    #[test]
+    fn optional_property_added() {
+        let a = record(r#"{ "a": { "type": "string" } }"#, r#"["a"]"#);
+        let b = record(
+            r#"{ "a": { "type": "string" }, "b": { "type": "string" } }"#,
+            r#"["a"]"#,
+        );
+        let changes = diff(&a, &b);
+        assert!(!is_breaking(&changes));
+        assert_eq!(suggest_revision(&a, &changes), Some(1));
+    }
+
+    #[test]
+    fn limits() {
+        let a = record(r#"{ "a": { "type": "string", "maxLength": 10 } }"#, "[]");
+        let tighter = record(r#"{ "a": { "type": "string", "maxLength": 5 } }"#, "[]");
+        let wider = record(r#"{ "a": { "type": "string", "maxLength": 20 } }"#, "[]");
+        assert!(is_breaking(&diff(&a, &tighter)));
+        assert!(!is_breaking(&diff(&a, &wider)));
+    }
+
+    #[test]
+    fn known_values_added() {
+        let a = record(r#"{ "a": { "type": "string" } }"#, "[]");
+        let b = record(
+            r#"{ "a": { "type": "string", "knownValues": ["x"] } }"#,
+            "[]",
+        );
+        let changes = diff(&a, &b);
+        assert_eq!(changes.len(), 1);
+        assert!(!is_breaking(&changes));
+    }
+
+    #[test]
+    fn closed_union_ref_removed() {
+        let a =
+            lexicon(r##"{ "main": { "type": "union", "refs": ["#a", "#b"], "closed": true } }"##);
+        let b = lexicon(r##"{ "main": { "type": "union", "refs": ["#a"], "closed": true } }"##);
+        assert!(is_breaking(&diff(&a, &b)));
+
+        let a = lexicon(r##"{ "main": { "type": "union", "refs": ["#a", "#b"] } }"##);
+        let b = lexicon(r##"{ "main": { "type": "union", "refs": ["#a"] } }"##);
+        assert!(!is_breaking(&diff(&a, &b)));
+    }
+
+    #[test]
+    fn record_key_changed() {
+        let a = lexicon(
+            r#"{ "main": { "type": "record", "key": "tid", "record": { "type": "object", "properties": {} } } }"#,
+        );
+        let b = lexicon(
+            r#"{ "main": { "type": "record", "key": "any", "record": { "type": "object", "properties": {} } } }"#,
+        );
+        assert!(is_breaking(&diff(&a, &b)));
+    }
+}
@@ -0,0 +1,119 @@
+use lexicon::{AtpObject, AtpParams, AtpTypes, Lexicon, RpcError};
+
+// markdown reference docs for a lexicon
+pub fn markdown(lexicon: &Lexicon) -> String {
+    let mut out = format!("# {}\n", lexicon.id);
+    if let Some(description) = &lexicon.description {
+        out.push_str(&format!("\n{description}\n"));
+    }
+
+    let mut names: Vec<&String> = lexicon.defs.keys().collect();
+    // main first, everything else in declaration order
+    names.sort_by_key(|x| *x != "main");
+
+    for name in names {
+        let def = &lexicon.defs[name];
+        out.push_str(&format!("\n## {name}\n\n`{}`\n", def.kind()));
+        if let Some(description) = def.description() {
+            out.push_str(&format!("\n{description}\n"));
+        }
+
+        match def {
+            AtpTypes::Object(x) => object(&mut out, x),
+            AtpTypes::Record(x) => {
+                out.push_str(&format!("\nrecord key: `{}`\n", x.key));
+                object(&mut out, &x.record);
+            }
+            AtpTypes::Query(x) => {
+                params(&mut out, &x.parameters);
+                errors(&mut out, &x.errors);
+            }
+            AtpTypes::Procedure(x) => {
+                params(&mut out, &x.parameters);
+                errors(&mut out, &x.errors);
+            }
+            AtpTypes::Subscription(x) => {
+                params(&mut out, &x.parameters);
+                errors(&mut out, &x.errors);
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+fn object(out: &mut String, object: &AtpObject) {
+    if object.properties.is_empty() {
+        return;
+    }
+    out.push_str("\n|name|type|required|description|\n|-|-|-|-|\n");
+
+    let required = object.required.clone().unwrap_or_default();
+    for (name, t) in &object.properties {
+        out.push_str(&format!(
+            "|{name}|{}|{}|{}|\n",
+            summary(t),
+            if required.contains(name) { "yes" } else { "" },
+            t.description()
+                .map_or(String::new(), |x| x.replace('\n', " "))
+        ));
+    }
+}
+
+fn params(out: &mut String, params: &Option<AtpParams>) {
+    let Some(params) = params else {
+        return;
+    };
+    out.push_str("\n### parameters\n\n|name|required|\n|-|-|\n");
+    let required = params.required.clone().unwrap_or_default();
+    for name in params.properties.keys() {
+        out.push_str(&format!(
+            "|{name}|{}|\n",
+            if required.contains(name) { "yes" } else { "" }
+        ));
+    }
+}
+
+fn errors(out: &mut String, errors: &Option<Vec<RpcError>>) {
+    let Some(errors) = errors else {
+        return;
+    };
+    out.push_str("\n### errors\n\n");
+    for error in errors {
+        match &error.description {
+            Some(description) => out.push_str(&format!("- `{}`: {description}\n", error.name)),
+            None => out.push_str(&format!("- `{}`\n", error.name)),
+        }
+    }
+}
+
+// short type description for tables
+fn summary(t: &AtpTypes) -> String {
+    match t {
+        AtpTypes::String(x) => match &x.format {
+            Some(format) => format!(
+                "string ({})",
+                serde_json::json!(format).as_str().unwrap_or_default()
+            ),
+            None => "string".to_string(),
+        },
+        AtpTypes::Array(x) => format!("array of {}", summary(&x.items)),
+        AtpTypes::Ref(x) => format!("[{0}]({0})", x.reference),
+        AtpTypes::Union(x) => format!("union of {}", x.refs.join(", ")),
+        other => other.kind().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pinksea() {
+        let lexicon: Lexicon =
+            serde_json::from_str(&std::fs::read_to_string("pinksea.json").unwrap()).unwrap();
+        let out = markdown(&lexicon);
+        assert!(out.starts_with("# com.shinolabs.pinksea.oekaki\n\n## main\n\n`record`\n"));
+        assert!(out.contains("|createdAt|string (datetime)|yes|The timestamp of creation.|\n"));
+    }
+}
@@ -0,0 +1,208 @@
+use ir::diagnostic::{Diagnostic, suggest};
+use lexicon::{IndexMap, RpcError};
+use tree_sitter::{Node, Range};
+
+// `errors RepoErrors { RepoNotFound; RepoTakendown: "..."; }`, included into queries and
+// procedures with `...RepoErrors`, and into other sets the same way
+#[derive(Debug, Default)]
+pub struct ErrorSets<'t> {
+    sets: IndexMap<String, Node<'t>>,
+}
+
+struct Walk<'a> {
+    src: &'a str,
+    // the sets being expanded, innermost last
+    stack: Vec<String>,
+    entries: Vec<Entry>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Walk<'a> {
+    fn new(src: &'a str) -> Walk<'a> {
+        Walk {
+            src,
+            stack: Vec::new(),
+            entries: Vec::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
+// an error on its way into an `errors` list, with where it was written and the set it came from
+struct Entry {
+    error: RpcError,
+    origin: Range,
+    from: Option<String>,
+}
+
+impl<'t> ErrorSets<'t> {
+    pub fn insert(&mut self, name: String, body: Node<'t>) -> Result<(), String> {
+        if self.sets.contains_key(&name) {
+            return Err(format!("error set `{name}` is already defined"));
+        }
+        self.sets.insert(name, body);
+        Ok(())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.sets.keys().map(|x| x.as_str())
+    }
+
+    // the errors of an `errors { ... }` body with every included set written out, in order
+    // a name that shows up twice is an error, unless it's the same error included along two paths
+    pub fn expand(&self, src: &str, body: &Node) -> (Vec<RpcError>, Vec<Diagnostic>) {
+        let mut walk = Walk::new(src);
+        self.collect(&mut walk, body, None);
+        let errors = walk.entries.into_iter().map(|x| x.error).collect();
+        (errors, walk.diagnostics)
+    }
+
+    // every set written out on its own, so mistakes in unused ones still show up
+    pub fn check(&self, src: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for (name, body) in &self.sets {
+            let mut walk = Walk::new(src);
+            walk.stack.push(name.clone());
+            self.collect(&mut walk, body, None);
+            diagnostics.extend(walk.diagnostics);
+        }
+        diagnostics
+    }
+
+    // `site` is the `...Set` that was written where the errors are used, duplicates are reported there
+    fn collect(&self, walk: &mut Walk, body: &Node, site: Option<(&str, Range)>) {
+        let src = walk.src;
+        let text = |x: &Node| src[x.byte_range()].to_string();
+        let mut cursor = body.walk();
+        for child in body.named_children(&mut cursor) {
+            match child.kind() {
+                "comment" => {}
+                "spread" => {
+                    let Some(name) = child.named_child(0).map(|x| text(&x)) else {
+                        continue;
+                    };
+                    let Some(set) = self.sets.get(&name) else {
+                        let message = match suggest(&name, self.names()) {
+                            Some(x) => format!("unknown error set `{name}`, did you mean `{x}`?"),
+                            None => format!("unknown error set `{name}`"),
+                        };
+                        walk.diagnostics
+                            .push(Diagnostic::error(message, child.range()));
+                        continue;
+                    };
+                    if walk.stack.contains(&name) {
+                        let message = format!("error set `{name}` ends up including itself");
+                        walk.diagnostics
+                            .push(Diagnostic::error(message, child.range()));
+                        continue;
+                    }
+                    walk.stack.push(name.clone());
+                    let site = site.unwrap_or((&name, child.range()));
+                    self.collect(walk, set, Some(site));
+                    walk.stack.pop();
+                }
+                "error" => {
+                    let Some(name) = child.child_by_field_name("name") else {
+                        continue;
+                    };
+                    let description = match child.child_by_field_name("description") {
+                        Some(x) => match ir::literal::string(&text(&x)) {
+                            Ok(x) => Some(x),
+                            Err(e) => {
+                                walk.diagnostics.push(Diagnostic::error(e, x.range()));
+                                None
+                            }
+                        },
+                        None => None,
+                    };
+                    let entry = Entry {
+                        error: RpcError {
+                            name: text(&name),
+                            description,
+                        },
+                        origin: child.range(),
+                        from: site.map(|(x, _)| x.to_string()),
+                    };
+                    match walk
+                        .entries
+                        .iter()
+                        .find(|x| x.error.name == entry.error.name)
+                    {
+                        Some(existing) if existing.origin == entry.origin => {}
+                        Some(existing) => {
+                            let place = |x: &Entry| match &x.from {
+                                Some(set) => format!("from `{set}`"),
+                                None => "written out".to_string(),
+                            };
+                            let message = format!(
+                                "error `{}` is listed twice, {} and {}",
+                                entry.error.name,
+                                place(existing),
+                                place(&entry)
+                            );
+                            let loc = site.map_or(child.range(), |(_, x)| x);
+                            walk.diagnostics.push(Diagnostic::error(message, loc));
+                        }
+                        None => walk.entries.push(entry),
+                    }
+                }
+                kind => walk.diagnostics.push(Diagnostic::error(
+                    format!("unexpected `{kind}` in errors, expected `Name;` or `...Set;`"),
+                    child.range(),
+                )),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lexicon::{AtpTypes, Catalog};
+
+    use crate::{compile::compile, parser};
+
+    fn compile_errors(src: &str) -> (Vec<String>, Vec<String>) {
+        let tree = parser().parse(src, None).unwrap();
+        let (lexicon, diagnostics) = compile(src, &tree, &Catalog::new());
+        let names = match &lexicon.defs["main"] {
+            AtpTypes::Procedure(x) => x.errors.iter().flatten().map(|x| x.name.clone()).collect(),
+            other => panic!("expected a procedure, got {other:?}"),
+        };
+        (names, diagnostics.into_iter().map(|x| x.message).collect())
+    }
+
+    #[test]
+    fn includes_sets() {
+        let (names, messages) = compile_errors(
+            r#"@com.example.test {
+                errors RepoErrors { RepoNotFound; ...AccountErrors; }
+                errors AccountErrors { AccountTakedown: "taken down"; }
+                procedure main { errors { ...RepoErrors; ...AccountErrors; Busy; } }
+            }"#,
+        );
+        assert!(messages.is_empty(), "{messages:?}");
+        assert_eq!(names, ["RepoNotFound", "AccountTakedown", "Busy"]);
+    }
+
+    #[test]
+    fn duplicates_and_cycles() {
+        let (names, messages) = compile_errors(
+            r#"@com.example.test {
+                errors A { NotFound; ...B; }
+                errors B { ...A; }
+                errors C { NotFound; }
+                procedure main { errors { ...C; NotFound; ...Missing; } }
+            }"#,
+        );
+        assert_eq!(names, ["NotFound"]);
+        assert_eq!(
+            messages,
+            [
+                "error set `A` ends up including itself",
+                "error set `B` ends up including itself",
+                "error `NotFound` is listed twice, from `C` and written out",
+                "unknown error set `Missing`",
+            ]
+        );
+    }
+}
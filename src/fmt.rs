@@ -0,0 +1,432 @@
+use tree_sitter::{Node, Parser};
+
+const INDENT: &str = "    ";
+
+// canonical param order for each type, params not listed here keep their source order after these
+fn param_order(name: &str) -> &'static [&'static str] {
+    match name {
+        "String" => &[
+            "format",
+            "len",
+            "graphemes",
+            "known",
+            "enum",
+            "default",
+            "const",
+        ],
+        "Integer" => &["range", "enum", "default", "const"],
+        "Boolean" => &["default", "const"],
+        "Bytes" => &["size"],
+        "Blob" => &["accept", "size"],
+        "Union" => &["refs", "closed"],
+        _ => &[],
+    }
+}
+
+pub fn format(src: &str) -> Result<String, String> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&tree_sitter_ana::LANGUAGE.into())
+        .expect("Error loading ana grammar");
+    let tree = parser.parse(src, None).unwrap();
+
+    if tree.root_node().has_error() {
+        return Err("refusing to format a file with syntax errors".to_string());
+    }
+
+    let mut f = Formatter {
+        src,
+        out: String::new(),
+        depth: 0,
+    };
+    f.source_file(&tree.root_node());
+    Ok(f.out)
+}
+
+struct Formatter<'a> {
+    src: &'a str,
+    out: String,
+    depth: usize,
+}
+
+impl Formatter<'_> {
+    fn text(&self, node: &Node) -> &str {
+        &self.src[node.byte_range()]
+    }
+
+    fn line(&mut self, s: &str) {
+        for _ in 0..self.depth {
+            self.out.push_str(INDENT);
+        }
+        self.out.push_str(s);
+        self.out.push('\n');
+    }
+
+    // comments on the same row as the previous node stay trailing, anything else gets its own line
+    fn comment(&mut self, node: &Node, prev_row: Option<usize>) {
+        let text = self.text(node).trim_end().to_string();
+        if prev_row == Some(node.start_position().row) && self.out.ends_with('\n') {
+            self.out.pop();
+            self.out.push(' ');
+            self.out.push_str(&text);
+            self.out.push('\n');
+        } else {
+            self.line(&text);
+        }
+    }
+
+    // runs `item` over every named child, keeping comments where they were and collapsing blank lines to at most one
+    fn block(&mut self, node: &Node, item: fn(&mut Self, &Node), blank_between: bool) {
+        let name = node.child_by_field_name("name");
+        let mut cursor = node.walk();
+        let mut prev: Option<Node> = None;
+        for child in node.named_children(&mut cursor) {
+            if Some(child) == name {
+                continue;
+            }
+            if child.kind() == "comment" {
+                if prev.is_some_and(|p| child.start_position().row > p.end_position().row + 1) {
+                    self.out.push('\n');
+                }
+                self.comment(&child, prev.map(|x| x.end_position().row));
+                prev = Some(child);
+                continue;
+            }
+            if let Some(p) = prev {
+                let gap = child.start_position().row > p.end_position().row + 1;
+                if gap || (blank_between && p.kind() != "comment") {
+                    self.out.push('\n');
+                }
+            }
+            item(self, &child);
+            prev = Some(child);
+        }
+    }
+
+    fn source_file(&mut self, node: &Node) {
+        self.block(node, Self::namespace, true);
+    }
+
+    fn namespace(&mut self, node: &Node) {
+        let Some(name) = node.child_by_field_name("name") else {
+            return self.verbatim(node);
+        };
+        let header = format!("@{} {{", self.text(&name));
+        self.line(&header);
+        self.depth += 1;
+        self.block(node, Self::def, true);
+        self.depth -= 1;
+        self.line("}");
+    }
+
+    fn def(&mut self, node: &Node) {
+        if node.kind() == "token" {
+            let Some(name) = node.child_by_field_name("name") else {
+                return self.verbatim(node);
+            };
+            let line = format!("token {};", self.text(&name));
+            return self.line(&line);
+        }
+        if node.kind() == "alias" {
+            let (Some(name), Some(typen)) = (
+                node.child_by_field_name("name"),
+                node.child_by_field_name("type"),
+            ) else {
+                return self.verbatim(node);
+            };
+            let line = format!("type {} = {};", self.text(&name), self.typen(&typen));
+            return self.line(&line);
+        }
+        if node.kind() == "query" || node.kind() == "procedure" {
+            return self.rpc(node);
+        }
+        if node.kind() == "errors" {
+            let (Some(name), Some(body)) = (
+                node.child_by_field_name("name"),
+                node.child_by_field_name("body"),
+            ) else {
+                return self.verbatim(node);
+            };
+            let header = format!("errors {} {{", self.text(&name));
+            self.line(&header);
+            self.depth += 1;
+            self.block(&body, Self::error, false);
+            self.depth -= 1;
+            return self.line("}");
+        }
+        let header = match (node.kind(), node.child_by_field_name("key")) {
+            ("object", _) => String::new(),
+            ("record", Some(key)) => format!("record({}) ", self.text(&key)),
+            ("record", None) => "record ".to_string(),
+            _ => return self.verbatim(node),
+        };
+        let (Some(name), Some(body)) = (
+            node.child_by_field_name("name"),
+            node.child_by_field_name("body"),
+        ) else {
+            return self.verbatim(node);
+        };
+
+        let mut cursor = node.walk();
+        let generics: Vec<&str> = node
+            .children_by_field_name("generic", &mut cursor)
+            .map(|x| self.text(&x))
+            .collect();
+        let mut header = format!("{header}{}", self.text(&name));
+        if !generics.is_empty() {
+            header.push_str(&format!("<{}>", generics.join(", ")));
+        }
+        let header = format!("{header} {{");
+        self.line(&header);
+        self.depth += 1;
+        self.block(&body, Self::property, false);
+        self.depth -= 1;
+        self.line("}");
+    }
+
+    // `paginated query name { params { ... } output { ... } }`, sections always come in the order below
+    fn rpc(&mut self, node: &Node) {
+        let mut cursor = node.walk();
+        let Some(name) = node.child_by_field_name("name") else {
+            return self.verbatim(node);
+        };
+        if node
+            .named_children(&mut cursor)
+            .any(|x| x.kind() == "comment")
+        {
+            return self.verbatim(node);
+        }
+        let mut header = format!("{} {} {{", node.kind(), self.text(&name));
+        if let Some(modifier) = node.child_by_field_name("modifier") {
+            header = format!("{} {header}", self.typen(&modifier));
+        }
+        self.line(&header);
+        self.depth += 1;
+        let mut first = true;
+        for section in ["params", "input", "output", "errors"] {
+            let Some(body) = node.child_by_field_name(section) else {
+                continue;
+            };
+            if !first {
+                self.out.push('\n');
+            }
+            first = false;
+            self.line(&format!("{section} {{"));
+            self.depth += 1;
+            match section {
+                "errors" => self.block(&body, Self::error, false),
+                _ => self.block(&body, Self::property, false),
+            }
+            self.depth -= 1;
+            self.line("}");
+        }
+        self.depth -= 1;
+        self.line("}");
+    }
+
+    // `RepoNotFound;`, `RepoTakendown: "...";` or `...RepoErrors;`
+    fn error(&mut self, node: &Node) {
+        if node.kind() == "spread"
+            && let Some(set) = node.named_child(0)
+        {
+            let line = format!("...{};", self.text(&set));
+            return self.line(&line);
+        }
+        let Some(name) = node.child_by_field_name("name") else {
+            return self.verbatim(node);
+        };
+        let line = match node.child_by_field_name("description") {
+            Some(description) => format!("{}: {};", self.text(&name), self.text(&description)),
+            None => format!("{};", self.text(&name)),
+        };
+        self.line(&line);
+    }
+
+    fn property(&mut self, node: &Node) {
+        if node.kind() == "spread"
+            && let Some(reference) = node.named_child(0)
+        {
+            let line = format!("...{};", self.text(&reference));
+            return self.line(&line);
+        }
+        let (Some(name), Some(typen)) = (
+            node.child_by_field_name("name"),
+            node.child_by_field_name("type"),
+        ) else {
+            return self.verbatim(node);
+        };
+        let marker = if node.kind() == "optional" { "?" } else { "" };
+        let line = format!("{}{marker}: {};", self.text(&name), self.typen(&typen));
+        self.line(&line);
+    }
+
+    // refs, inline objects and types with comments in them are kept as written
+    fn typen(&self, node: &Node) -> String {
+        if node.kind() != "type" || contains_comment(node) {
+            return self.text(node).to_string();
+        }
+
+        let mut cursor = node.walk();
+        let name = self.text(&node.named_child(0).unwrap()).to_string();
+        let mut params: Vec<(String, String)> = node
+            .children_by_field_name("param", &mut cursor)
+            .map(|x| {
+                (
+                    self.text(&x.named_child(0).unwrap()).to_string(),
+                    self.value(&x.named_child(1).unwrap()),
+                )
+            })
+            .collect();
+        let order = param_order(&name);
+        params.sort_by_key(|(k, _)| order.iter().position(|x| x == k).unwrap_or(order.len()));
+
+        let mut out = name;
+        let mut cursor = node.walk();
+        let arguments: Vec<String> = node
+            .children_by_field_name("argument", &mut cursor)
+            .map(|x| self.typen(&x))
+            .collect();
+        if !arguments.is_empty() {
+            out.push_str(&format!("<{}>", arguments.join(", ")));
+        }
+        if !params.is_empty() {
+            let params: Vec<String> = params.iter().map(|(k, v)| format!("{k}={v}")).collect();
+            out.push_str(&format!("({})", params.join(", ")));
+        }
+
+        let mut cursor = node.walk();
+        if node.children(&mut cursor).any(|x| x.kind() == "[") {
+            out.push_str(&format!("[{}]", self.slice(node)));
+        }
+        out
+    }
+
+    fn value(&self, node: &Node) -> String {
+        match node.kind() {
+            "slice" => self.slice(node),
+            "list" => {
+                let mut cursor = node.walk();
+                let items: Vec<String> = node
+                    .named_children(&mut cursor)
+                    .map(|x| self.value(&x))
+                    .collect();
+                format!("[{}]", items.join(", "))
+            }
+            _ => self.text(node).to_string(),
+        }
+    }
+
+    fn slice(&self, node: &Node) -> String {
+        let bound = |field| {
+            node.child_by_field_name(field)
+                .map_or(String::new(), |x| self.text(&x).to_string())
+        };
+        format!("{}..{}", bound("min"), bound("max"))
+    }
+
+    // anything the formatter doesn't understand is passed through untouched
+    fn verbatim(&mut self, node: &Node) {
+        let text = self.text(node).trim().to_string();
+        let mut lines = text.lines();
+        if let Some(first) = lines.next() {
+            self.line(first);
+        }
+        for rest in lines {
+            self.out.push_str(rest);
+            self.out.push('\n');
+        }
+    }
+}
+
+fn contains_comment(node: &Node) -> bool {
+    let mut cursor = node.walk();
+    node.kind() == "comment" || node.children(&mut cursor).any(|x| contains_comment(&x))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_spacing_and_params() {
+        let src = "@com.example.test{\nfoo{a :String( len = ..640 ,format=\"did\" ) ;\n\n\n  b?: Integer[1..2];}}";
+        let out = format(src).unwrap();
+        assert_eq!(
+            out,
+            "@com.example.test {\n    foo {\n        a: String(format=\"did\", len=..640);\n\n        b?: Integer[1..2];\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn idempotent() {
+        let src = std::fs::read_to_string("example.ana").unwrap();
+        let once = format(&src).unwrap();
+        assert_eq!(once, format(&once).unwrap());
+    }
+
+    #[test]
+    fn keeps_comments() {
+        let src = "@com.example.test {\n    // leading\n    foo {\n        a: String; // trailing\n    }\n}\n";
+        assert_eq!(format(src).unwrap(), src);
+    }
+
+    #[test]
+    fn comments_inside_types() {
+        let src = "@com.example.test {\n    foo {\n        a: String( // shown as is\n            default=\"a  b\"\n        );\n        b: { c: String; }; // inline\n    }\n}\n";
+        assert_eq!(format(src).unwrap(), src);
+    }
+
+    #[test]
+    fn tokens_and_known_values() {
+        let src = "@com.example.test {\n  token   foo ;\n    bar {\n        a: String(known=[ #foo,\"baz\" ]);\n    }\n}\n";
+        assert_eq!(
+            format(src).unwrap(),
+            "@com.example.test {\n    token foo;\n\n    bar {\n        a: String(known=[#foo, \"baz\"]);\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn aliases() {
+        let src = "@com.example.test {\n  type   Tag=String( len=..640 ) ;\n    bar {\n        a: Tag[..10];\n    }\n}\n";
+        assert_eq!(
+            format(src).unwrap(),
+            "@com.example.test {\n    type Tag = String(len=..640);\n\n    bar {\n        a: Tag[..10];\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn generics() {
+        let src = "@com.example.test {\n    Page< T,U > {\n        items: T[..100];\n    }\n\n    bar {\n        a: Page< #post , String(len=..10) >;\n    }\n}\n";
+        assert_eq!(
+            format(src).unwrap(),
+            "@com.example.test {\n    Page<T, U> {\n        items: T[..100];\n    }\n\n    bar {\n        a: Page<#post, String(len=..10)>;\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn queries() {
+        let src = "@com.example.test {\n    paginated( limit=1..50 ) query getFeed {\n  params { actor: Did; }\n        output { feed: String[..10]; }\n    }\n}\n";
+        assert_eq!(
+            format(src).unwrap(),
+            "@com.example.test {\n    paginated(limit=1..50) query getFeed {\n        params {\n            actor: Did;\n        }\n\n        output {\n            feed: String[..10];\n        }\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn error_sets() {
+        let src = "@com.example.test {\n    errors RepoErrors { RepoNotFound ; RepoTakendown :\"gone\"; }\n\n    procedure deleteRepo {\n        errors { ...RepoErrors; Busy; }\n    }\n}\n";
+        assert_eq!(
+            format(src).unwrap(),
+            "@com.example.test {\n    errors RepoErrors {\n        RepoNotFound;\n        RepoTakendown: \"gone\";\n    }\n\n    procedure deleteRepo {\n        errors {\n            ...RepoErrors;\n            Busy;\n        }\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn spreads() {
+        let src = "@com.example.test {\n    bar {\n        ...  #common ;\n        a: String;\n    }\n}\n";
+        assert_eq!(
+            format(src).unwrap(),
+            "@com.example.test {\n    bar {\n        ...#common;\n        a: String;\n    }\n}\n"
+        );
+    }
+}
@@ -0,0 +1,312 @@
+use ir::props::FORMAT_ALIASES;
+use lexicon::{AtpObject, AtpString, AtpTypes, Lexicon, RecordKey, StringFormats};
+
+// turns a lexicon back into ana source
+// anything ana can't express yet is kept as a comment so nothing is lost silently
+pub fn import(lexicon: &Lexicon) -> String {
+    let mut out = format!("@{} {{\n", lexicon.id);
+    if let Some(description) = &lexicon.description {
+        comment(&mut out, 1, description);
+    }
+
+    for (i, (name, def)) in lexicon.defs.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        match def {
+            AtpTypes::Object(object) => {
+                description(&mut out, 1, &object.description);
+                object_def(&mut out, lexicon, "", name, object);
+            }
+            AtpTypes::Record(record) => {
+                description(&mut out, 1, &record.description);
+                let keyword = match &record.key {
+                    RecordKey::Tid => "record ".to_string(),
+                    key => format!("record({key}) "),
+                };
+                object_def(&mut out, lexicon, &keyword, name, &record.record);
+            }
+            AtpTypes::Token(token) => {
+                description(&mut out, 1, &token.description);
+                out.push_str(&format!("    token {name};\n"));
+            }
+            other => comment(
+                &mut out,
+                1,
+                &format!("{name}: {} definitions can't be imported yet", other.kind()),
+            ),
+        }
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+fn object_def(out: &mut String, lexicon: &Lexicon, keyword: &str, name: &str, object: &AtpObject) {
+    out.push_str(&format!("    {keyword}{name} {{\n"));
+
+    let required = object.required.clone().unwrap_or_default();
+    for (prop, value) in &object.properties {
+        description(out, 2, &describe(value));
+        let marker = if required.contains(prop) { "" } else { "?" };
+        out.push_str(&format!(
+            "        {prop}{marker}: {};\n",
+            typen(lexicon, value)
+        ));
+    }
+    out.push_str("    }\n");
+}
+
+fn typen(lexicon: &Lexicon, t: &AtpTypes) -> String {
+    match t {
+        AtpTypes::Null(_) => "Null".to_string(),
+        AtpTypes::Boolean(x) => {
+            let mut params = Vec::new();
+            if let Some(default) = x.default {
+                params.push(format!("default={default}"));
+            }
+            if let Some(constant) = x.constant {
+                params.push(format!("const={constant}"));
+            }
+            with_params("Boolean", params)
+        }
+        AtpTypes::Integer(x) => {
+            let mut params = Vec::new();
+            if x.minimum.is_some() || x.maximum.is_some() {
+                params.push(format!("range={}", slice(x.minimum, x.maximum)));
+            }
+            if let Some(enumeration) = &x.enumeration {
+                let values: Vec<String> = enumeration.iter().map(|x| x.to_string()).collect();
+                params.push(format!("enum=[{}]", values.join(", ")));
+            }
+            if let Some(default) = x.default {
+                params.push(format!("default={default}"));
+            }
+            if let Some(constant) = x.constant {
+                params.push(format!("const={constant}"));
+            }
+            with_params("Integer", params)
+        }
+        AtpTypes::String(x) => string(lexicon, x),
+        AtpTypes::Bytes(x) => {
+            let mut params = Vec::new();
+            if x.min_length.is_some() || x.max_length.is_some() {
+                params.push(format!("size={}", slice(x.min_length, x.max_length)));
+            }
+            with_params("Bytes", params)
+        }
+        AtpTypes::CidLink(_) => "CidLink".to_string(),
+        AtpTypes::Blob(x) => {
+            let mut params = Vec::new();
+            match x.accept.as_deref() {
+                Some([accept]) => params.push(format!("accept={accept:?}")),
+                Some(accept) if !accept.is_empty() => {
+                    let accept: Vec<String> = accept.iter().map(|x| format!("{x:?}")).collect();
+                    params.push(format!("accept=[{}]", accept.join(", ")));
+                }
+                _ => {}
+            }
+            if let Some(size) = x.max_size {
+                params.push(format!("size={size}"));
+            }
+            with_params("Blob", params)
+        }
+        AtpTypes::Array(x) => {
+            let bounds = if x.min_length.is_some() || x.max_length.is_some() {
+                slice(x.min_length, x.max_length)
+            } else {
+                "..".to_string()
+            };
+            format!("{}[{bounds}]", typen(lexicon, &x.items))
+        }
+        AtpTypes::Ref(x) => x.reference.clone(),
+        AtpTypes::Union(x) => {
+            let mut params = vec![format!("refs=[{}]", x.refs.join(", "))];
+            if let Some(closed) = x.closed {
+                params.push(format!("closed={closed}"));
+            }
+            with_params("Union", params)
+        }
+        _ => "Unknown".to_string(),
+    }
+}
+
+fn string(lexicon: &Lexicon, x: &AtpString) -> String {
+    let mut params = Vec::new();
+    if x.min_length.is_some() || x.max_length.is_some() {
+        params.push(format!("len={}", slice(x.min_length, x.max_length)));
+    }
+    if x.min_graphemes.is_some() || x.max_graphemes.is_some() {
+        params.push(format!(
+            "graphemes={}",
+            slice(x.min_graphemes, x.max_graphemes)
+        ));
+    }
+    if let Some(known) = &x.known_values {
+        let known: Vec<String> = known.iter().map(|x| known_value(lexicon, x)).collect();
+        params.push(format!("known=[{}]", known.join(", ")));
+    }
+    if let Some(enumeration) = &x.enumeration {
+        let values: Vec<String> = enumeration.iter().map(|x| format!("{x:?}")).collect();
+        params.push(format!("enum=[{}]", values.join(", ")));
+    }
+    if let Some(default) = &x.default {
+        params.push(format!("default={default:?}"));
+    }
+    if let Some(constant) = &x.constant {
+        params.push(format!("const={constant:?}"));
+    }
+
+    // formats have their own shorthand types when nothing else is set
+    if params.is_empty()
+        && let Some(alias) = x.format.as_ref().and_then(format_alias)
+    {
+        return alias.to_string();
+    }
+    match &x.format {
+        Some(format) => {
+            params.insert(0, format!("format={:?}", format_name(format)));
+            with_params("String", params)
+        }
+        None => with_params("String", params),
+    }
+}
+
+// tokens of the lexicon being imported go back to `#name`, everything else stays a plain string
+fn known_value(lexicon: &Lexicon, value: &str) -> String {
+    let local = value
+        .strip_prefix(&lexicon.id)
+        .and_then(|x| x.strip_prefix('#'))
+        .filter(|x| matches!(lexicon.defs.get(*x), Some(AtpTypes::Token(_))));
+    match local {
+        Some(name) => format!("#{name}"),
+        None => format!("{value:?}"),
+    }
+}
+
+fn format_alias(f: &StringFormats) -> Option<&'static str> {
+    FORMAT_ALIASES
+        .iter()
+        .find(|(_, format)| format == f)
+        .map(|(alias, _)| *alias)
+}
+
+fn format_name(f: &StringFormats) -> String {
+    serde_json::to_value(f)
+        .ok()
+        .and_then(|x| x.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn with_params(name: &str, params: Vec<String>) -> String {
+    if params.is_empty() {
+        name.to_string()
+    } else {
+        format!("{name}({})", params.join(", "))
+    }
+}
+
+fn slice<T: ToString>(min: Option<T>, max: Option<T>) -> String {
+    format!(
+        "{}..{}",
+        min.map_or(String::new(), |x| x.to_string()),
+        max.map_or(String::new(), |x| x.to_string())
+    )
+}
+
+// comments for the parts of a property ana can't carry
+fn describe(t: &AtpTypes) -> Option<String> {
+    let mut notes = Vec::new();
+    if let Some(description) = t.description() {
+        notes.push(description.clone());
+    }
+    match t {
+        AtpTypes::Array(x) => notes.extend(describe(&x.items)),
+        AtpTypes::Object(_) | AtpTypes::Params(_) | AtpTypes::Token(_) => {
+            notes.push(format!("was a {} in the source lexicon", t.kind()))
+        }
+        _ => {}
+    }
+    (!notes.is_empty()).then(|| notes.join("\n"))
+}
+
+fn description(out: &mut String, depth: usize, description: &Option<String>) {
+    if let Some(description) = description {
+        comment(out, depth, description);
+    }
+}
+
+fn comment(out: &mut String, depth: usize, text: &str) {
+    for line in text.lines() {
+        out.push_str(&"    ".repeat(depth));
+        out.push_str("// ");
+        out.push_str(line);
+        out.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pinksea() {
+        let lexicon: Lexicon =
+            serde_json::from_str(&std::fs::read_to_string("pinksea.json").unwrap()).unwrap();
+        let out = import(&lexicon);
+        assert!(out.starts_with("@com.shinolabs.pinksea.oekaki {\n"));
+        assert!(out.contains("    record main {\n"));
+        assert!(out.contains("        createdAt: DateTime;\n"));
+        assert!(out.contains("        tags?: String(len=..640)[..10];\n"));
+    }
+
+    #[test]
+    fn tokens() {
+        let lexicon: Lexicon = serde_json::from_str(
+            r##"{ "lexicon": 1, "id": "com.example.test", "defs": {
+                "main": { "type": "object", "properties": {
+                    "reason": { "type": "string", "knownValues": ["com.example.test#spam", "com.example.test#main", "other"] }
+                } },
+                "spam": { "type": "token", "description": "unwanted" }
+            } }"##,
+        )
+        .unwrap();
+        let out = import(&lexicon);
+        assert!(out.contains(
+            "        reason?: String(known=[#spam, \"com.example.test#main\", \"other\"]);\n"
+        ));
+        assert!(out.contains("    // unwanted\n    token spam;\n"));
+    }
+
+    #[test]
+    fn enums() {
+        let lexicon: Lexicon = serde_json::from_str(
+            r#"{ "lexicon": 1, "id": "com.example.test", "defs": {
+                "main": { "type": "object", "required": ["a", "b", "c"], "properties": {
+                    "a": { "type": "string", "enum": ["x", "y"], "default": "x" },
+                    "b": { "type": "integer", "enum": [1, 2], "const": 2 },
+                    "c": { "type": "boolean", "default": true }
+                } }
+            } }"#,
+        )
+        .unwrap();
+        let out = import(&lexicon);
+        assert!(out.contains("        a: String(enum=[\"x\", \"y\"], default=\"x\");\n"));
+        assert!(out.contains("        b: Integer(enum=[1, 2], const=2);\n"));
+        assert!(out.contains("        c: Boolean(default=true);\n"));
+    }
+
+    #[test]
+    fn unions() {
+        let lexicon: Lexicon = serde_json::from_str(
+            r##"{ "lexicon": 1, "id": "com.example.test", "defs": {
+                "main": { "type": "object", "required": ["embed"], "properties": {
+                    "embed": { "type": "union", "refs": ["#a", "com.example.b"], "closed": true }
+                } }
+            } }"##,
+        )
+        .unwrap();
+        let out = import(&lexicon);
+        assert!(out.contains("        embed: Union(refs=[#a, com.example.b], closed=true);\n"));
+    }
+}
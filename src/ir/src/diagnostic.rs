@@ -0,0 +1,80 @@
+use std::fmt::Display;
+
+use tree_sitter::{Node, Range};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub loc: Range,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>, loc: Range) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            message: message.into(),
+            loc,
+        }
+    }
+
+    pub fn warning(message: impl Into<String>, loc: Range) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Warning,
+            message: message.into(),
+            loc,
+        }
+    }
+}
+
+// the closest candidate within a couple of edits, for "did you mean" hints
+// swapped letters count as one edit, `Tga` is one away from `Tag`
+pub fn suggest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let max = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|x| (strsim::osa_distance(name, x), x))
+        .filter(|(distance, _)| *distance <= max)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, x)| x)
+}
+
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(|x| x.severity == Severity::Error)
+}
+
+// every ERROR and MISSING node under `node`, the lowering skips over them
+pub fn syntax_errors(node: &Node, diagnostics: &mut Vec<Diagnostic>) {
+    if node.is_missing() {
+        diagnostics.push(Diagnostic::error(
+            format!("missing `{}`", node.kind()),
+            node.range(),
+        ));
+        return;
+    }
+    if node.is_error() {
+        diagnostics.push(Diagnostic::error("syntax error", node.range()));
+        return;
+    }
+    if node.has_error() {
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            syntax_errors(&child, diagnostics);
+        }
+    }
+}
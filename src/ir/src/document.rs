@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use ropey::Rope;
+use tree_sitter::{InputEdit, Node, Parser, Point, Tree};
+
+use crate::{
+    NodeHelpers,
+    object::{Object, parse_object},
+    props::{
+        alias::{Alias, Aliases},
+        types::generic::Template,
+    },
+};
+
+// an open ana file, as held by the language server
+// edits are applied to the rope and the tree in place, then only the defs that
+// tree-sitter reports as changed are lowered again
+
+#[derive(Debug)]
+pub struct Def {
+    pub object: Result<Object, ()>,
+    // start byte of the def when it was lowered, locations inside `object` are
+    // relative to this revision until the def is lowered again
+    pub lowered_at: usize,
+}
+
+pub struct Document {
+    pub text: Rope,
+    pub tree: Tree,
+    pub defs: HashMap<String, Def>,
+    parser: Parser,
+}
+
+impl Document {
+    pub fn new(src: &str) -> Document {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_ana::LANGUAGE.into())
+            .expect("error loading ana grammar");
+        let tree = parser.parse(src, None).unwrap();
+
+        let mut document = Document {
+            text: Rope::from_str(src),
+            tree,
+            defs: HashMap::new(),
+            parser,
+        };
+        document.lower(&[]);
+        document
+    }
+
+    // byte offset of an lsp position, lsp counts columns in utf-16 code units
+    pub fn offset(&self, line: usize, character: usize) -> usize {
+        let line = line.min(self.text.len_lines().saturating_sub(1));
+        let start = self.text.line_to_char(line);
+        let column = self
+            .text
+            .line(line)
+            .utf16_cu_to_char(character.min(self.text.line(line).len_utf16_cu()));
+        self.text.char_to_byte(start + column)
+    }
+
+    fn point(&self, byte: usize) -> Point {
+        let row = self.text.byte_to_line(byte);
+        Point {
+            row,
+            column: byte - self.text.line_to_byte(row),
+        }
+    }
+
+    // replaces the bytes in start..end with text, returns the names of the defs that were lowered again
+    pub fn edit(&mut self, start: usize, end: usize, text: &str) -> Vec<String> {
+        let start_position = self.point(start);
+        let old_end_position = self.point(end);
+
+        let start_char = self.text.byte_to_char(start);
+        let end_char = self.text.byte_to_char(end);
+        self.text.remove(start_char..end_char);
+        self.text.insert(start_char, text);
+
+        let new_end_byte = start + text.len();
+        let edit = InputEdit {
+            start_byte: start,
+            old_end_byte: end,
+            new_end_byte,
+            start_position,
+            old_end_position,
+            new_end_position: self.point(new_end_byte),
+        };
+        self.tree.edit(&edit);
+
+        let text = &self.text;
+        let tree = self
+            .parser
+            .parse_with_options(
+                &mut |byte, _| {
+                    if byte >= text.len_bytes() {
+                        return &[][..];
+                    }
+                    let (chunk, chunk_start, _, _) = text.chunk_at_byte(byte);
+                    &chunk.as_bytes()[byte - chunk_start..]
+                },
+                Some(&self.tree),
+                None,
+            )
+            .unwrap();
+
+        let mut changed: Vec<std::ops::Range<usize>> = self
+            .tree
+            .changed_ranges(&tree)
+            .map(|x| x.start_byte..x.end_byte)
+            .collect();
+        changed.push(start..new_end_byte);
+        self.tree = tree;
+
+        self.lower(&changed)
+    }
+
+    fn lower(&mut self, changed: &[std::ops::Range<usize>]) -> Vec<String> {
+        let src = self.text.to_string();
+        let mut lowered = Vec::new();
+        let mut seen = Vec::new();
+
+        let tree = self.tree.clone();
+        let Some(namespace) = tree.root_node().child(0) else {
+            self.defs.clear();
+            return lowered;
+        };
+
+        let touched = |def: &Node| {
+            changed
+                .iter()
+                .any(|x| x.start <= def.end_byte() && def.start_byte() <= x.end)
+        };
+        // every def can use the aliases, changing one lowers everything again
+        let mut aliases = Aliases::default();
+        let mut everything = false;
+        let mut cursor = namespace.walk();
+        for def in namespace.children_by_field_name("body", &mut cursor) {
+            let inserted = match def.kind() {
+                "alias" => Alias::from(&src, &def).and_then(|x| aliases.insert(x)),
+                "object" if is_generic(&def) => {
+                    Template::from(&src, &def).and_then(|x| aliases.insert_template(x))
+                }
+                _ => continue,
+            };
+            // broken ones are left out, `ana check` reports them
+            let _ = inserted;
+            everything |= touched(&def);
+        }
+
+        let mut cursor = namespace.walk();
+        for def in namespace.children_by_field_name("body", &mut cursor) {
+            let Some(name) = def.child_by_field_name("name").map(|x| x.str(&src)) else {
+                continue;
+            };
+            // generic defs are only lowered where they're used
+            if is_generic(&def) {
+                continue;
+            }
+            seen.push(name.clone());
+
+            if !everything && !touched(&def) && self.defs.contains_key(&name) {
+                continue;
+            }
+
+            self.defs.insert(
+                name.clone(),
+                Def {
+                    object: lower_def(&src, &def, &aliases),
+                    lowered_at: def.start_byte(),
+                },
+            );
+            lowered.push(name);
+        }
+
+        self.defs.retain(|name, _| seen.contains(name));
+        lowered
+    }
+}
+
+fn lower_def(src: &str, node: &Node, aliases: &Aliases) -> Result<Object, ()> {
+    match node.kind() {
+        "object" | "record" => parse_object(src, node, aliases),
+        _ => Err(()),
+    }
+}
+
+fn is_generic(def: &Node) -> bool {
+    def.child_by_field_name("generic").is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SRC: &str = "@com.example.test {\n    foo { a: String }\n    bar { b: Integer }\n}\n";
+
+    #[test]
+    fn lowers_every_def() {
+        let document = Document::new(SRC);
+        assert!(document.defs.contains_key("foo"));
+        assert!(document.defs.contains_key("bar"));
+    }
+
+    #[test]
+    fn relowers_changed_def() {
+        let mut document = Document::new(SRC);
+        let start = SRC.find("Integer").unwrap();
+        let lowered = document.edit(start, start + "Integer".len(), "String");
+        assert!(lowered == vec!["bar".to_string()]);
+        assert!(document.text.to_string().contains("b: String"));
+    }
+
+    #[test]
+    fn offset_counts_utf16() {
+        let document = Document::new("@a.b.c {\n    // ü😀x\n}\n");
+        let line = "    // ü😀x";
+        assert!(document.offset(1, 10) == "@a.b.c {\n".len() + line.find('x').unwrap());
+    }
+}
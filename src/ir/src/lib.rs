@@ -1,7 +1,10 @@
 use num_traits::PrimInt;
-use std::{fmt::Debug, str::FromStr};
+use std::fmt::Display;
 use tree_sitter::{Node, Range};
 
+pub mod diagnostic;
+pub mod document;
+pub mod literal;
 pub mod object;
 pub mod props;
 
@@ -17,52 +20,52 @@ impl NodeHelpers for Node<'_> {
     }
 }
 
+// the value of a string literal with escapes resolved, see `literal::string`
 pub fn extract_string(src: &str, node: &Node) -> Option<String> {
     match node.kind() {
-        "string" => {
-            let start = node.start_byte();
-            let end = node.end_byte();
-            Some(String::from(&src[start + 1..end - 1]))
-        }
+        "string" => literal::string(&node.str(src)).ok(),
         _ => None,
     }
 }
 
-pub fn extract_integer<T: PrimInt + FromStr>(src: &str, node: &Node) -> Option<T>
-where
-    T: PrimInt + FromStr,
-    <T as FromStr>::Err: Debug,
-{
+// None when the literal doesn't fit in `T`, use `parse_integer` to report why
+pub fn extract_integer<T: PrimInt>(src: &str, node: &Node) -> Option<T> {
     match node.kind() {
-        "integer" => Some(node.str(&src).parse::<T>().unwrap()),
+        "integer" => literal::integer(&node.str(src))
+            .ok()
+            .and_then(num_traits::cast),
         _ => None,
     }
 }
 
+// integer literals, including separators, hex and sizes, anything that doesn't fit in 64 bits is reported instead of panicking
+pub fn parse_integer(src: &str, node: &Node) -> Result<i64, String> {
+    literal::integer(&node.str(src))
+}
+
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub struct Slice {
-    pub start: Option<i32>,
-    pub end: Option<i32>,
+    pub start: Option<i64>,
+    pub end: Option<i64>,
     pub loc: Option<Range>,
 }
 
 impl Slice {
-    fn from(src: &str, node: &Node) -> Slice {
-        // TODO fix slice start and end bytes, take from min and max instead of node
-        // slice can be an anonymous node so we don't match on it and everything explodes instead
-        // this means any node can be passed in and you get a start and end range always
-        let min = node
-            .child_by_field_name("min")
-            .and_then(|x| extract_integer(&src, &x));
-        let max = node
-            .child_by_field_name("max")
-            .and_then(|x| extract_integer(&src, &x));
-
-        Slice {
-            start: min,
-            end: max,
+    // TODO fix slice start and end bytes, take from min and max instead of node
+    // slice can be an anonymous node so we don't match on it and everything explodes instead
+    // this means any node can be passed in and you get a start and end range always
+    // a bound that doesn't fit in 64 bits is an error
+    fn parse(src: &str, node: &Node) -> Result<Slice, String> {
+        let bound = |field| {
+            node.child_by_field_name(field)
+                .map(|x| parse_integer(src, &x))
+                .transpose()
+        };
+        Ok(Slice {
+            start: bound("min")?,
+            end: bound("max")?,
             loc: Some(node.range()),
-        }
+        })
     }
 
     fn empty() -> Slice {
@@ -72,43 +75,100 @@ impl Slice {
             loc: None,
         }
     }
+
+    // both ends are inclusive, same as the lexicon min/max fields they turn into
+    pub fn contains(&self, n: i64) -> bool {
+        self.start.is_none_or(|x| x <= n) && self.end.is_none_or(|x| n <= x)
+    }
+
+    // lengths and sizes are unsigned 32 bit in lexicons, other bounds can't be written out
+    pub fn fits_u32(&self) -> bool {
+        [self.start, self.end]
+            .into_iter()
+            .flatten()
+            .all(|x| u32::try_from(x).is_ok())
+    }
 }
 
-#[derive(Debug)]
+impl Display for Slice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let bound = |x: Option<i64>| x.map_or(String::new(), |x| x.to_string());
+        write!(f, "{}..{}", bound(self.start), bound(self.end))
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Param {
     pub name: String,
     pub value: ParamKind,
     pub loc: Range,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum ParamKind {
     String(String),
-    Integer(i32),
+    // negative values included, e.g. -5
+    Integer(i64),
+    Boolean(bool),
     Slice(Slice),
+    // e.g. [#foo, "bar"]
+    List(Vec<ParamKind>),
+    // e.g. #foo or com.example.defs#foo
+    Ref(String),
+    // a bare word, e.g. did
+    Ident(String),
+}
+
+impl ParamKind {
+    fn from(src: &str, node: &Node) -> Result<ParamKind, String> {
+        match node.kind() {
+            "string" => literal::string(&node.str(src)).map(ParamKind::String),
+            "integer" => parse_integer(src, node).map(ParamKind::Integer),
+            "boolean" => Ok(ParamKind::Boolean(node.str(src) == "true")),
+            "slice" => Slice::parse(src, node).map(ParamKind::Slice),
+            "ref" => Ok(ParamKind::Ref(node.str(src))),
+            "identifier" => Ok(ParamKind::Ident(node.str(src))),
+            "list" => {
+                let mut cursor = node.walk();
+                node.named_children(&mut cursor)
+                    .filter(|x| x.kind() != "comment")
+                    .map(|x| ParamKind::from(src, &x))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(ParamKind::List)
+            }
+            kind => Err(format!("`{kind}` can't be used as a param value")),
+        }
+    }
+
+    // for diagnostics, e.g. "expected a slice, got an integer"
+    pub fn describe(&self) -> &'static str {
+        match self {
+            ParamKind::String(_) => "a string",
+            ParamKind::Integer(_) => "an integer",
+            ParamKind::Boolean(_) => "a boolean",
+            ParamKind::Slice(_) => "a range",
+            ParamKind::List(_) => "a list",
+            ParamKind::Ref(_) => "a ref",
+            ParamKind::Ident(_) => "an identifier",
+        }
+    }
 }
 
 impl Param {
-    // e.g. foo="bar"
-    pub fn from(src: &str, node: &Node) -> Result<Param, ()> {
+    // e.g. foo="bar", a value that can't be parsed comes back as the message to report
+    pub fn from(src: &str, node: &Node) -> Result<Param, String> {
         match node.kind() {
             "param" => {
-                let name = node.named_child(0).unwrap().str(&src);
-                let value = node.named_child(1).unwrap();
-                let kind = match value.kind() {
-                    "string" => Ok(ParamKind::String(extract_string(&src, &value).unwrap())),
-                    "integer" => Ok(ParamKind::Integer(extract_integer(&src, &value).unwrap())),
-                    "slice" => Ok(ParamKind::Slice(Slice::from(&src, &value))),
-                    _ => Err(()),
+                let (Some(name), Some(value)) = (node.named_child(0), node.named_child(1)) else {
+                    return Err("params are written `name=value`".to_string());
                 };
-
                 Ok(Param {
-                    name,
-                    value: kind?,
+                    name: name.str(src),
+                    value: ParamKind::from(src, &value)?,
                     loc: node.range(),
                 })
             }
-            _ => Err(()),
+            kind => Err(format!("expected a param, got `{kind}`")),
         }
     }
 }
@@ -144,16 +204,30 @@ mod tests {
         let src = "@@[ 42 ]@@";
         let tree = parse(&src);
         let node = unwrap_harness(&tree);
-        assert!(42 == extract_integer(src, &node).unwrap());
+        assert!(42 == extract_integer::<i32>(src, &node).unwrap());
+    }
+
+    #[test]
+    fn slice_parse_test() {
+        let src = "@@[ 1..2 ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        assert!(1 == Slice::parse(src, &node).unwrap().start.unwrap());
+        assert!(2 == Slice::parse(src, &node).unwrap().end.unwrap());
     }
 
     #[test]
-    fn slice_from_test() {
+    fn slice_contains_test() {
         let src = "@@[ 1..2 ]@@";
         let tree = parse(&src);
         let node = unwrap_harness(&tree);
-        assert!(1 == Slice::from(src, &node).start.unwrap());
-        assert!(2 == Slice::from(src, &node).end.unwrap());
+        let slice = Slice::parse(src, &node).unwrap();
+        assert!(!slice.contains(0));
+        assert!(slice.contains(1));
+        assert!(slice.contains(2));
+        assert!(!slice.contains(3));
+        assert!(Slice::empty().contains(i64::MAX));
+        assert!(slice.to_string() == "1..2");
     }
 
     #[test]
@@ -166,4 +240,83 @@ mod tests {
         assert!(param.loc.start_byte == 4);
         assert!(param.loc.end_byte == 10);
     }
+
+    #[test]
+    fn param_negative_test() {
+        let src = "@@[ foo=-42 ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let param = Param::from(src, &node).unwrap();
+        assert!(param.value == ParamKind::Integer(-42));
+    }
+
+    #[test]
+    fn param_overflow_test() {
+        let src = "@@[ foo=9223372036854775808 ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        assert!(
+            Param::from(src, &node).unwrap_err()
+                == "`9223372036854775808` doesn't fit in a 64 bit integer"
+        );
+
+        let src = "@@[ foo=..99999999999999999999 ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        assert!(Param::from(src, &node).is_err());
+    }
+
+    #[test]
+    fn param_size_test() {
+        let src = "@@[ size=1MiB ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        assert!(Param::from(src, &node).unwrap().value == ParamKind::Integer(1048576));
+
+        let src = "@@[ size=1M ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        assert!(
+            Param::from(src, &node).unwrap_err()
+                == "`1M` is ambiguous, write `1MB` (1000000) or `1MiB` (1048576)"
+        );
+    }
+
+    #[test]
+    fn param_string_test() {
+        let src = r#"@@[ foo="a\tb" ]@@"#;
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        assert!(Param::from(src, &node).unwrap().value == ParamKind::String("a\tb".to_string()));
+
+        let src = r#"@@[ foo="a\qb" ]@@"#;
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        assert!(Param::from(src, &node).unwrap_err() == "unknown escape `\\q`");
+    }
+
+    #[test]
+    fn param_ident_test() {
+        let src = "@@[ format=did ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let param = Param::from(src, &node).unwrap();
+        assert!(param.value == ParamKind::Ident("did".to_string()));
+    }
+
+    #[test]
+    fn param_list_test() {
+        let src = "@@[ known=[#foo, \"bar\", com.example.defs#baz] ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let param = Param::from(src, &node).unwrap();
+        assert!(
+            param.value
+                == ParamKind::List(vec![
+                    ParamKind::Ref("#foo".to_string()),
+                    ParamKind::String("bar".to_string()),
+                    ParamKind::Ref("com.example.defs#baz".to_string()),
+                ])
+        );
+    }
 }
@@ -0,0 +1,281 @@
+// decimal and binary byte sizes, e.g. `500KB` and `1MiB`
+const UNITS: &[(&str, i128)] = &[
+    ("B", 1),
+    ("KB", 1_000),
+    ("MB", 1_000_000),
+    ("GB", 1_000_000_000),
+    ("TB", 1_000_000_000_000),
+    ("KiB", 1 << 10),
+    ("MiB", 1 << 20),
+    ("GiB", 1 << 30),
+    ("TiB", 1 << 40),
+];
+
+fn unit(name: &str) -> Option<i128> {
+    UNITS.iter().find(|(x, _)| *x == name).map(|(_, x)| *x)
+}
+
+// integer literals as written in ana: `-5`, `1_000_000`, `0xff` or a size such as `1MiB`
+pub fn integer(text: &str) -> Result<i64, String> {
+    let (negative, rest) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let (radix, digits, suffix) = match rest.strip_prefix("0x").or(rest.strip_prefix("0X")) {
+        Some(hex) => (16, hex, ""),
+        None => {
+            let split = rest
+                .find(|c: char| !c.is_ascii_digit() && c != '_')
+                .unwrap_or(rest.len());
+            (10, &rest[..split], &rest[split..])
+        }
+    };
+
+    if digits.is_empty() || digits.starts_with('_') || digits.ends_with('_') {
+        return Err(format!(
+            "`{text}` isn't a valid number, `_` can only separate digits"
+        ));
+    }
+    let digits = digits.replace('_', "");
+    if !digits.chars().all(|c| c.is_digit(radix)) {
+        return Err(format!("`{text}` isn't a valid hex number"));
+    }
+
+    let scale = match suffix {
+        "" => 1,
+        suffix => match unit(suffix) {
+            Some(x) => x,
+            None => return Err(unknown_unit(text, rest, suffix)),
+        },
+    };
+    let overflow = || format!("`{text}` doesn't fit in a 64 bit integer");
+    let value = i128::from_str_radix(&digits, radix)
+        .ok()
+        .and_then(|x| x.checked_mul(scale))
+        .ok_or_else(overflow)?;
+    let value = if negative { -value } else { value };
+    i64::try_from(value).map_err(|_| overflow())
+}
+
+// `1M` could be either `1MB` or `1MiB`, and `1Mb` could be bits, so both spellings are offered
+fn unknown_unit(text: &str, rest: &str, suffix: &str) -> String {
+    let number = &text[..text.len() - suffix.len()];
+    let upper = suffix.to_ascii_uppercase();
+    let prefix = upper
+        .strip_suffix("IB")
+        .or(upper.strip_suffix('B'))
+        .unwrap_or(&upper);
+    let candidates: Vec<String> = [format!("{prefix}B"), format!("{prefix}iB")]
+        .into_iter()
+        .filter(|x| unit(x).is_some())
+        .map(|x| match integer(&format!("{number}{x}")) {
+            Ok(value) => format!("`{number}{x}` ({value})"),
+            Err(_) => format!("`{number}{x}`"),
+        })
+        .collect();
+    if candidates.is_empty() {
+        let units: Vec<&str> = UNITS.iter().map(|(x, _)| *x).collect();
+        return format!(
+            "unknown unit `{suffix}` in `{rest}`, expected one of {}",
+            units.join(", ")
+        );
+    }
+    format!("`{text}` is ambiguous, write {}", candidates.join(" or "))
+}
+
+// string literals as written in ana, quotes included:
+// `"..."` with escapes, `r"..."` or `r#"..."#` raw, and `"""` blocks that span lines
+// the escapes are the same as rust's, so `{:?}` of a string is always a valid literal
+pub fn string(text: &str) -> Result<String, String> {
+    if let Some(raw) = text.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let fence = "#".repeat(hashes);
+        return raw
+            .strip_prefix(&format!("{fence}\""))
+            .and_then(|x| x.strip_suffix(&format!("\"{fence}")))
+            .map(str::to_string)
+            .ok_or_else(|| format!("`{text}` isn't a valid raw string"));
+    }
+    if let Some(block) = text
+        .strip_prefix("\"\"\"")
+        .and_then(|x| x.strip_suffix("\"\"\""))
+    {
+        return unescape(&dedent(block));
+    }
+    match text.strip_prefix('"').and_then(|x| x.strip_suffix('"')) {
+        Some(inner) => unescape(inner),
+        None => Err(format!("`{text}` isn't a valid string")),
+    }
+}
+
+// the line break after the opening quotes and the line holding the closing quotes are dropped,
+// then the indentation shared by every non-blank line and the closing quotes is removed
+fn dedent(block: &str) -> String {
+    let block = block
+        .strip_prefix("\r\n")
+        .or(block.strip_prefix('\n'))
+        .unwrap_or(block);
+    let mut lines: Vec<&str> = block
+        .split('\n')
+        .map(|x| x.strip_suffix('\r').unwrap_or(x))
+        .collect();
+    let closing = match lines.last() {
+        Some(last) if lines.len() > 1 && last.trim().is_empty() => lines.pop(),
+        _ => None,
+    };
+    let indent = lines
+        .iter()
+        .filter(|x| !x.trim().is_empty())
+        .chain(closing.as_ref())
+        .map(|x| x.len() - x.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|x| x.get(indent..).unwrap_or(x.trim_start()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn unescape(s: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some('0') => out.push('\0'),
+            Some('\\') => out.push('\\'),
+            Some('"') => out.push('"'),
+            Some('u') => {
+                let rest = chars.as_str();
+                let hex = rest
+                    .strip_prefix('{')
+                    .and_then(|x| x.split_once('}'))
+                    .map(|(hex, _)| hex)
+                    .ok_or("unicode escapes are written `\\u{1F600}`")?;
+                let c = u32::from_str_radix(hex, 16)
+                    .ok()
+                    .filter(|_| (1..=6).contains(&hex.len()))
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!("`\\u{{{hex}}}` isn't a valid unicode character"))?;
+                out.push(c);
+                chars = rest[hex.len() + 2..].chars();
+            }
+            Some(other) => return Err(format!("unknown escape `\\{other}`")),
+            None => return Err("a string can't end with a lone `\\`".to_string()),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain() {
+        assert_eq!(integer("42"), Ok(42));
+        assert_eq!(integer("-42"), Ok(-42));
+        assert_eq!(integer("1_048_576"), Ok(1048576));
+        assert_eq!(integer("0xff"), Ok(255));
+        assert_eq!(integer("0xFF_FF"), Ok(65535));
+        assert_eq!(integer("-9223372036854775808"), Ok(i64::MIN));
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(integer("500KB"), Ok(500_000));
+        assert_eq!(integer("1MiB"), Ok(1048576));
+        assert_eq!(integer("1_000B"), Ok(1000));
+        assert_eq!(integer("2GiB"), Ok(2147483648));
+    }
+
+    #[test]
+    fn ambiguous() {
+        assert_eq!(
+            integer("1M").unwrap_err(),
+            "`1M` is ambiguous, write `1MB` (1000000) or `1MiB` (1048576)"
+        );
+        assert_eq!(
+            integer("5kb").unwrap_err(),
+            "`5kb` is ambiguous, write `5KB` (5000) or `5KiB` (5120)"
+        );
+        assert_eq!(
+            integer("1b").unwrap_err(),
+            "`1b` is ambiguous, write `1B` (1)"
+        );
+        assert_eq!(
+            integer("3px").unwrap_err(),
+            "unknown unit `px` in `3px`, expected one of B, KB, MB, GB, TB, KiB, MiB, GiB, TiB"
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            integer("9223372036854775808").unwrap_err(),
+            "`9223372036854775808` doesn't fit in a 64 bit integer"
+        );
+        assert!(integer("10000000TiB").is_err());
+        assert!(integer("99999999999999999999999999999999999999999").is_err());
+        assert!(integer("1__").is_err());
+        assert!(integer("_1").is_err());
+        assert!(integer("0x").is_err());
+        assert!(integer("0xfg").is_err());
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(string(r#""wow""#), Ok("wow".to_string()));
+        assert_eq!(
+            string(r#""a \"quoted\" word""#),
+            Ok("a \"quoted\" word".to_string())
+        );
+        assert_eq!(string(r#""a\nb\t\\""#), Ok("a\nb\t\\".to_string()));
+        assert_eq!(string(r#""\u{1F600}\u{e9}""#), Ok("😀é".to_string()));
+        assert_eq!(string(r#""\q""#).unwrap_err(), "unknown escape `\\q`");
+        assert_eq!(
+            string(r#""\u{D800}""#).unwrap_err(),
+            "`\\u{D800}` isn't a valid unicode character"
+        );
+        assert!(string(r#""\u1234""#).is_err());
+    }
+
+    #[test]
+    fn debug_round_trip() {
+        for s in [
+            "plain",
+            "a \"b\" \\ c",
+            "tab\tnew\nline\r\0",
+            "\u{7f}\u{200b}é😀",
+        ] {
+            assert_eq!(string(&format!("{s:?}")), Ok(s.to_string()));
+        }
+    }
+
+    #[test]
+    fn raw() {
+        assert_eq!(string(r#"r"C:\path\n""#), Ok(r"C:\path\n".to_string()));
+        assert_eq!(string(r##"r#"say "hi""#"##), Ok(r#"say "hi""#.to_string()));
+        assert!(string(r##"r#"unclosed""##).is_err());
+    }
+
+    #[test]
+    fn blocks() {
+        let src = "\"\"\"\n        first line\n          indented\n\n        last \\\"line\\\"\n        \"\"\"";
+        assert_eq!(
+            string(src),
+            Ok("first line\n  indented\n\nlast \"line\"".to_string())
+        );
+        // the closing quotes count towards the indentation
+        let src = "\"\"\"\n    a\n  \"\"\"";
+        assert_eq!(string(src), Ok("  a".to_string()));
+        assert_eq!(string("\"\"\"one line\"\"\""), Ok("one line".to_string()));
+    }
+}
@@ -1,37 +1,44 @@
-use std::collections::HashMap;
-
+use lexicon::IndexMap;
 use tree_sitter::{Node, Range};
 
 use crate::{
     NodeHelpers,
-    props::{Prop, parse_properties},
+    diagnostic::{Diagnostic, syntax_errors},
+    props::{Prop, alias::Aliases, types::object},
 };
 
-pub fn parse_object(src: &str, node: &Node) -> Result<Object, ()> {
-    // this function extracts generic type info from a type node
-    // generic types are not part of the IR and should be converted to a specific type
+// a def as the language server sees it, `aliases` are the ones declared in the same file
+// anything that can't be lowered ends up in the diagnostics, only a def that isn't an
+// object at all is an error
+pub fn parse_object(src: &str, node: &Node, aliases: &Aliases) -> Result<Object, ()> {
+    if !matches!(node.kind(), "object" | "record") {
+        return Err(());
+    }
+    let (Some(name), Some(body)) = (
+        node.child_by_field_name("name"),
+        node.child_by_field_name("body"),
+    ) else {
+        return Err(());
+    };
 
-    match node.kind() {
-        "object" => {
-            let name = node.named_child(0).unwrap().str(&src);
-            //panic!("{:#?}", &node.named_child(1).unwrap().to_sexp());
-            let props = parse_properties(&src, &node.named_child(1).unwrap());
-            //panic!("{:#?}", props);
+    let body = object::Type::parse(src, &body, aliases);
+    let mut diagnostics = Vec::new();
+    syntax_errors(node, &mut diagnostics);
+    diagnostics.extend(body.check());
 
-            Ok(Object {
-                name,
-                props,
-                loc: node.range(),
-            })
-        }
-        _ => panic!("{:?}", node.kind()),
-    }
+    Ok(Object {
+        name: name.str(src),
+        props: body.props,
+        diagnostics,
+        loc: node.range(),
+    })
 }
 
 #[derive(Debug)]
 pub struct Object {
     pub name: String,
-    pub props: HashMap<String, Prop>,
+    pub props: IndexMap<String, Prop>,
+    pub diagnostics: Vec<Diagnostic>,
     pub loc: Range,
 }
 
@@ -60,7 +67,7 @@ mod tests {
         let src = "@@[ image { foo: String(default=\"bar\") } ]@@";
         let tree = parse(&src);
         let node = unwrap_harness(&tree);
-        let object = parse_object(src, &node).unwrap();
+        let object = parse_object(src, &node, &Aliases::default()).unwrap();
         assert!(object.name == "image");
         //panic!("{:#?}", object);
         assert!(object.props.contains_key("foo"));
@@ -69,4 +76,23 @@ mod tests {
             _ => false,
         });
     }
+
+    #[test]
+    fn keeps_order() {
+        let src = "@@[ image { z: String; a: Integer; m: Boolean } ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let object = parse_object(src, &node, &Aliases::default()).unwrap();
+        assert!(object.props.keys().collect::<Vec<_>>() == vec!["z", "a", "m"]);
+    }
+
+    #[test]
+    fn broken() {
+        let src = "@@[ image { foo: ; bar: String } ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let object = parse_object(src, &node, &Aliases::default()).unwrap();
+        assert!(!object.diagnostics.is_empty());
+        assert!(object.props.contains_key("bar"));
+    }
 }
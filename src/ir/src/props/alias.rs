@@ -0,0 +1,306 @@
+use lexicon::IndexMap;
+use tree_sitter::{Node, Range};
+
+use crate::{
+    NodeHelpers, ParamKind,
+    diagnostic::{Diagnostic, suggest},
+    props::{
+        GenericProp, is_builtin,
+        types::{generic::Template, reference},
+    },
+};
+
+// `type Tag = String(len=..640);`, expanded wherever `Tag` is used as a type
+#[derive(Debug, Clone)]
+pub struct Alias {
+    pub name: String,
+    pub target: Target,
+    pub loc: Range,
+}
+
+#[derive(Debug, Clone)]
+pub enum Target {
+    Type(GenericProp),
+    // e.g. `type Image = #image;`, or a generic param given a ref
+    Ref(reference::Type),
+}
+
+impl Alias {
+    pub fn from(src: &str, node: &Node) -> Result<Alias, String> {
+        let (Some(name), Some(target)) = (
+            node.child_by_field_name("name"),
+            node.child_by_field_name("type"),
+        ) else {
+            return Err("type aliases are written `type Name = String(...);`".to_string());
+        };
+        let invalid = |_| format!("`{}` isn't a type", target.str(src));
+        let target = match target.kind() {
+            "ref" => Target::Ref(reference::Type::from(src, &target).map_err(invalid)?),
+            _ => Target::Type(GenericProp::from(src, &target).map_err(invalid)?),
+        };
+        Ok(Alias {
+            name: name.str(src),
+            target,
+            loc: node.range(),
+        })
+    }
+}
+
+// every name that stands for something else in a type position: aliases, generic defs,
+// and while a generic def is being filled in, its params
+#[derive(Debug, Default, Clone)]
+pub struct Aliases<'t> {
+    aliases: IndexMap<String, Alias>,
+    templates: IndexMap<String, Template<'t>>,
+    // generic defs being filled in right now, a def that uses itself would never finish
+    pub instantiating: Vec<String>,
+}
+
+impl<'t> Aliases<'t> {
+    pub fn insert(&mut self, alias: Alias) -> Result<(), String> {
+        self.claim(&alias.name)?;
+        self.aliases.insert(alias.name.clone(), alias);
+        Ok(())
+    }
+
+    pub fn insert_template(&mut self, template: Template<'t>) -> Result<(), String> {
+        self.claim(&template.name)?;
+        self.templates.insert(template.name.clone(), template);
+        Ok(())
+    }
+
+    fn claim(&self, name: &str) -> Result<(), String> {
+        if is_builtin(name) {
+            return Err(format!("`{name}` is a built-in type"));
+        }
+        if self.aliases.contains_key(name) || self.templates.contains_key(name) {
+            return Err(format!("type `{name}` is already defined"));
+        }
+        Ok(())
+    }
+
+    // a generic param, it shadows anything else with the same name
+    pub fn bind(&mut self, name: &str, target: Target, loc: Range) {
+        self.templates.shift_remove(name);
+        let alias = Alias {
+            name: name.to_string(),
+            target,
+            loc,
+        };
+        self.aliases.insert(name.to_string(), alias);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Alias> {
+        self.aliases.get(name)
+    }
+
+    pub fn template(&self, name: &str) -> Option<&Template<'t>> {
+        self.templates.get(name)
+    }
+
+    // the ref an alias stands for, once `expand` has gone through any aliases in front of it
+    pub fn reference(&self, name: &str) -> Option<&reference::Type> {
+        match self.get(name).map(|x| &x.target) {
+            Some(Target::Ref(x)) => Some(x),
+            _ => None,
+        }
+    }
+
+    // every alias, in the order they were declared
+    pub fn iter(&self) -> impl Iterator<Item = &Alias> {
+        self.aliases.values()
+    }
+
+    // swaps the alias at the head of `prop` for what it stands for, aliases of aliases included
+    // params given at the use site refine the alias, they can narrow its ranges but not change anything else
+    pub fn expand(&self, mut prop: GenericProp) -> GenericProp {
+        let mut seen: Vec<String> = Vec::new();
+        while let Some(alias) = self.get(&prop.name) {
+            let Target::Type(target) = &alias.target else {
+                break;
+            };
+            if seen.contains(&alias.name) {
+                let message = format!("type `{}` refers to itself", alias.name);
+                prop.diagnostics.push(Diagnostic::error(message, alias.loc));
+                return unknown(prop);
+            }
+            seen.push(alias.name.clone());
+            prop = refine(&alias.name, target, prop);
+        }
+
+        let names = self.aliases.keys().chain(self.templates.keys());
+        if !is_builtin(&prop.name)
+            && self.get(&prop.name).is_none()
+            && let Some(name) = suggest(&prop.name, names.map(|x| x.as_str()))
+        {
+            let message = format!("unknown type `{}`, did you mean `{name}`?", prop.name);
+            prop.diagnostics.push(Diagnostic::error(message, prop.loc));
+            return unknown(prop);
+        }
+        prop
+    }
+}
+
+// an alias that can't be expanded still has to become something, its params have already been reported
+fn unknown(mut prop: GenericProp) -> GenericProp {
+    prop.name = "Unknown".to_string();
+    prop.params.clear();
+    prop
+}
+
+fn refine(alias: &str, target: &GenericProp, refinement: GenericProp) -> GenericProp {
+    let mut prop = target.clone();
+    prop.loc = refinement.loc;
+    prop.diagnostics.extend(refinement.diagnostics);
+
+    let mut params: Vec<_> = refinement.params.into_values().collect();
+    params.sort_by_key(|x| x.loc.start_byte);
+    for param in params {
+        if let Some(existing) = prop.params.get(param.name.as_str()) {
+            let conflict = match (&existing.value, &param.value) {
+                (a, b) if a == b => None,
+                (ParamKind::Slice(wide), ParamKind::Slice(narrow)) => {
+                    let start = wide
+                        .start
+                        .is_none_or(|x| narrow.start.is_some_and(|y| x <= y));
+                    let end = wide.end.is_none_or(|x| narrow.end.is_some_and(|y| y <= x));
+                    (!start || !end).then(|| {
+                        format!(
+                            "`{name}={narrow}` is wider than `{name}={wide}` from `{}`",
+                            alias,
+                            name = param.name,
+                        )
+                    })
+                }
+                _ => Some(format!(
+                    "`{}` is already set by `{alias}`, only ranges can be narrowed",
+                    param.name
+                )),
+            };
+            if let Some(message) = conflict {
+                prop.diagnostics.push(Diagnostic::error(message, param.loc));
+                continue;
+            }
+        }
+        prop.params
+            .insert(param.name.clone().into_boxed_str(), param);
+    }
+
+    if refinement.array {
+        if prop.array {
+            let message = format!("`{alias}` is already an array");
+            prop.diagnostics
+                .push(Diagnostic::error(message, refinement.loc));
+        } else {
+            prop.array = true;
+            prop.slice = refinement.slice;
+        }
+    }
+    prop
+}
+
+#[cfg(test)]
+mod tests {
+    use tree_sitter::{Parser, Tree};
+
+    use super::*;
+    use crate::props::PropKind;
+
+    fn unwrap_harness(tree: &Tree) -> Node {
+        tree.root_node().child(1).unwrap()
+    }
+
+    fn parse(src: &str) -> Tree {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_ana::LANGUAGE.into())
+            .expect("error loading ana grammar");
+        parser.parse(src, None).unwrap()
+    }
+
+    fn aliases(defs: &[&str]) -> Aliases<'static> {
+        let mut aliases = Aliases::default();
+        for def in defs {
+            let src = format!("@@[ {def} ]@@");
+            let tree = parse(&src);
+            let alias = Alias::from(&src, &unwrap_harness(&tree)).unwrap();
+            aliases.insert(alias).unwrap();
+        }
+        aliases
+    }
+
+    fn expand(aliases: &Aliases, src: &str) -> GenericProp {
+        let src = format!("@@[ {src} ]@@");
+        let tree = parse(&src);
+        aliases.expand(GenericProp::from(&src, &unwrap_harness(&tree)).unwrap())
+    }
+
+    fn messages(prop: &GenericProp) -> Vec<String> {
+        prop.diagnostics.iter().map(|x| x.message.clone()).collect()
+    }
+
+    #[test]
+    fn expands() {
+        let aliases = aliases(&["type Tag = String(len=..640);"]);
+        let prop = expand(&aliases, "Tag[..10]");
+        assert!(prop.name == "String");
+        assert!(prop.array);
+        assert!(prop.slice.end == Some(10));
+        let PropKind::Array(array) = PropKind::from(prop) else {
+            panic!("expected an array");
+        };
+        let PropKind::String(string) = *array.items else {
+            panic!("expected strings");
+        };
+        assert!(string.length.end == Some(640));
+    }
+
+    #[test]
+    fn refines() {
+        let aliases = aliases(&[
+            "type Tag = String(len=..640);",
+            "type Short = Tag(len=..64, graphemes=..10);",
+        ]);
+        let prop = expand(&aliases, "Short(format=did)");
+        assert!(messages(&prop).is_empty());
+        let PropKind::String(string) = PropKind::from(prop) else {
+            panic!("expected a string");
+        };
+        assert!(string.length.end == Some(64));
+        assert!(string.graphemes.end == Some(10));
+        assert!(string.format.is_some());
+    }
+
+    #[test]
+    fn conflicts() {
+        let aliases = aliases(&["type UserHandle = String(format=handle, len=10..640);"]);
+        let prop = expand(&aliases, "UserHandle(format=did, len=..100)");
+        assert!(
+            messages(&prop)
+                == vec![
+                    "`format` is already set by `UserHandle`, only ranges can be narrowed",
+                    "`len=..100` is wider than `len=10..640` from `UserHandle`",
+                ]
+        );
+    }
+
+    #[test]
+    fn cycles_and_typos() {
+        let aliases = aliases(&["type A = B;", "type B = A;", "type Tag = String;"]);
+        let prop = expand(&aliases, "A");
+        assert!(messages(&prop) == vec!["type `A` refers to itself"]);
+        assert!(prop.name == "Unknown");
+
+        let prop = expand(&aliases, "Tga");
+        assert!(messages(&prop) == vec!["unknown type `Tga`, did you mean `Tag`?"]);
+    }
+
+    #[test]
+    fn builtin_names() {
+        let mut aliases = aliases(&["type Tag = String;"]);
+        let src = "@@[ type String = Integer; ]@@";
+        let tree = parse(src);
+        let alias = Alias::from(src, &unwrap_harness(&tree)).unwrap();
+        assert!(aliases.insert(alias).unwrap_err() == "`String` is a built-in type");
+    }
+}
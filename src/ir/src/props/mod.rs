@@ -1,56 +1,282 @@
 use std::collections::HashMap;
 
+use alias::Aliases;
+use lexicon::{AtpTypes, StringFormats};
 use tree_sitter::{Node, Range};
-use types::{blob, boolean, integer, null, string};
+use types::{
+    array, blob, boolean, bytes, cid_link, generic, integer, null, object, reference, string,
+    union, unknown,
+};
 
-use crate::{NodeHelpers, Param, Slice};
+use crate::{
+    NodeHelpers, Param, ParamKind, Slice,
+    diagnostic::{Diagnostic, suggest},
+};
 
+pub mod alias;
 pub mod types;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct Prop {
     // e.g. foo: String
     pub name: String,
     pub value: PropKind,
+    pub optional: bool,
     pub loc: Range,
 }
 
 #[derive(Debug, PartialEq)]
 pub enum PropKind {
-    // these are the concrete atproto types for the most part
+    // every atproto field type, the primary types only appear as defs
+    Array(array::Type),
     Blob(blob::Type),
     Boolean(boolean::Type),
-    String(string::Type),
+    Bytes(bytes::Type),
+    CidLink(cid_link::Type),
     Integer(integer::Type),
     Null(null::Type),
+    Object(object::Type),
+    Ref(reference::Type),
+    String(string::Type),
+    Union(union::Type),
+    Unknown(unknown::Type),
+    // a generic def filled in at its use site, e.g. `Page<#post>`
+    Generic(generic::Type),
+}
+
+// names that can be used as field types, the string format aliases come on top of these
+const TYPES: &[&str] = &[
+    "Array", "Blob", "Boolean", "Bytes", "CidLink", "Integer", "Null", "Object", "String", "Union",
+    "Unknown",
+];
+
+// shorthands for strings with a format, e.g. `Did` is `String(format="did")`
+pub const FORMAT_ALIASES: &[(&str, StringFormats)] = &[
+    ("AtIdentifier", StringFormats::AtIdentifier),
+    ("AtUri", StringFormats::AtUri),
+    ("Cid", StringFormats::Cid),
+    ("DateTime", StringFormats::Datetime),
+    ("Did", StringFormats::Did),
+    ("Handle", StringFormats::Handle),
+    ("Nsid", StringFormats::Nsid),
+    ("Tid", StringFormats::Tid),
+    ("RecordKey", StringFormats::RecordKey),
+    ("Uri", StringFormats::Uri),
+    ("Language", StringFormats::Language),
+];
+
+pub fn format_alias(name: &str) -> Option<StringFormats> {
+    FORMAT_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map(|(_, format)| format.clone())
+}
+
+// only usable as defs, see `PropKind::from`
+const PRIMARY_TYPES: &[&str] = &[
+    "Params",
+    "Token",
+    "Record",
+    "Query",
+    "Procedure",
+    "Subscription",
+];
+
+// names a type alias can't take
+pub fn is_builtin(name: &str) -> bool {
+    TYPES.contains(&name) || PRIMARY_TYPES.contains(&name) || format_alias(name).is_some()
 }
 
 impl From<GenericProp> for PropKind {
-    fn from(value: GenericProp) -> Self {
-        match value.name.to_lowercase().as_str() {
-            "blob" => PropKind::Blob(blob::Type::from(value)),
-            "boolean" => PropKind::Boolean(boolean::Type::from(value)),
-            "string" => PropKind::String(string::Type::from(value)),
-            "integer" => PropKind::Integer(integer::Type::from(value)),
-            "null" => PropKind::Null(null::Type::from(value)),
-            _ => panic!("unknown type {}", value.name),
+    fn from(mut value: GenericProp) -> Self {
+        if value.array {
+            // the brackets belong to the array, everything else to its items
+            value.array = false;
+            let (length, loc) = (value.slice, value.loc);
+            value.slice = Slice::empty();
+            return PropKind::Array(array::Type::of(PropKind::from(value), length, loc));
+        }
+        if let Some(format) = format_alias(&value.name) {
+            let mut string = string::Type::from(value);
+            string.format = Some(format);
+            return PropKind::String(string);
+        }
+
+        match value.name.as_str() {
+            "Array" => PropKind::Array(array::Type::from(value)),
+            "Blob" => PropKind::Blob(blob::Type::from(value)),
+            "Boolean" => PropKind::Boolean(boolean::Type::from(value)),
+            "Bytes" => PropKind::Bytes(bytes::Type::from(value)),
+            "CidLink" => PropKind::CidLink(cid_link::Type::from(value)),
+            "Integer" => PropKind::Integer(integer::Type::from(value)),
+            "Null" => PropKind::Null(null::Type::from(value)),
+            "Object" => PropKind::Object(object::Type::from(value)),
+            "String" => PropKind::String(string::Type::from(value)),
+            "Union" => PropKind::Union(union::Type::from(value)),
+            "Unknown" => PropKind::Unknown(unknown::Type::from(value)),
+            name => {
+                let message = match name {
+                    name if PRIMARY_TYPES.contains(&name) => {
+                        format!("`{name}` can only be used as a definition, not as a field type")
+                    }
+                    _ => {
+                        let aliases = FORMAT_ALIASES.iter().map(|(alias, _)| *alias);
+                        let candidates = TYPES.iter().copied().chain(aliases);
+                        match suggest(name, candidates) {
+                            Some(x) => format!("unknown type `{name}`, did you mean `{x}`?"),
+                            None => format!("unknown type `{name}`"),
+                        }
+                    }
+                };
+                PropKind::Unknown(unknown::Type {
+                    description: None,
+                    diagnostics: vec![Diagnostic::error(message, value.loc)],
+                    loc: value.loc,
+                })
+            }
+        }
+    }
+}
+
+impl PropKind {
+    // a type node or a ref node
+    pub fn parse(src: &str, node: &Node) -> Result<PropKind, ()> {
+        PropKind::parse_with(src, node, &Aliases::default())
+    }
+
+    // same as `parse`, with type aliases expanded and generic defs filled in
+    pub fn parse_with(src: &str, node: &Node, aliases: &Aliases) -> Result<PropKind, ()> {
+        match node.kind() {
+            "ref" => Ok(PropKind::Ref(reference::Type::from(src, node)?)),
+            "object_body" => Ok(PropKind::Object(object::Type::parse(src, node, aliases))),
+            _ => {
+                let prop = GenericProp::from(src, node)?;
+                // `#post[..50]`, an array of refs
+                if let Some(name) = node.child_by_field_name("name")
+                    && name.kind() == "ref"
+                {
+                    let reference = reference::Type::from(src, &name)?;
+                    return Ok(PropKind::reference(&reference, prop));
+                }
+                if let Some(template) = aliases.template(&prop.name) {
+                    return Ok(generic::instantiate(src, node, template, aliases, prop));
+                }
+                let mut prop = aliases.expand(prop);
+                if node.child_by_field_name("argument").is_some() {
+                    let message = format!(
+                        "`{}` isn't generic, it can't take type arguments",
+                        prop.name
+                    );
+                    prop.diagnostics.push(Diagnostic::error(message, prop.loc));
+                }
+                match aliases.reference(&prop.name) {
+                    Some(reference) => Ok(PropKind::reference(reference, prop)),
+                    None => Ok(PropKind::from(prop)),
+                }
+            }
+        }
+    }
+
+    // a name that stands for a ref, `T` in a generic def given `#post` or `type Image = #image;`
+    fn reference(reference: &reference::Type, mut prop: GenericProp) -> PropKind {
+        let mut reference = reference.clone();
+        reference.loc = prop.loc;
+        let mut kind = PropKind::Ref(reference);
+        if !prop.params.is_empty() {
+            let message = format!("`{}` is a ref, it can't take params", prop.name);
+            prop.diagnostics.push(Diagnostic::error(message, prop.loc));
+        }
+        if !prop.diagnostics.is_empty() {
+            kind = PropKind::Unknown(unknown::Type {
+                description: None,
+                diagnostics: prop.diagnostics,
+                loc: prop.loc,
+            });
+        }
+        if prop.array {
+            kind = PropKind::Array(array::Type::of(kind, prop.slice, prop.loc));
+        }
+        kind
+    }
+
+    // constraints that contradict each other, e.g. a default outside of the allowed range
+    pub fn check(&self) -> Vec<Diagnostic> {
+        match self {
+            PropKind::Array(x) => x.check(),
+            PropKind::Blob(x) => x.check(),
+            PropKind::Boolean(x) => x.check(),
+            PropKind::Bytes(x) => x.check(),
+            PropKind::CidLink(x) => x.check(),
+            PropKind::Integer(x) => x.check(),
+            PropKind::Null(x) => x.check(),
+            PropKind::Object(x) => x.check(),
+            PropKind::Ref(_) => Vec::new(),
+            PropKind::String(x) => x.check(),
+            PropKind::Union(x) => x.check(),
+            PropKind::Unknown(x) => x.check(),
+            PropKind::Generic(x) => x.check(),
+        }
+    }
+
+    pub fn loc(&self) -> Range {
+        match self {
+            PropKind::Array(x) => x.loc,
+            PropKind::Blob(x) => x.loc,
+            PropKind::Boolean(x) => x.loc,
+            PropKind::Bytes(x) => x.loc,
+            PropKind::CidLink(x) => x.loc,
+            PropKind::Integer(x) => x.loc,
+            PropKind::Null(x) => x.loc,
+            PropKind::Object(x) => x.loc,
+            PropKind::Ref(x) => x.loc,
+            PropKind::String(x) => x.loc,
+            PropKind::Union(x) => x.loc,
+            PropKind::Unknown(x) => x.loc,
+            PropKind::Generic(x) => x.loc,
+        }
+    }
+}
+
+impl Into<AtpTypes> for PropKind {
+    fn into(self) -> AtpTypes {
+        match self {
+            PropKind::Array(x) => x.into(),
+            PropKind::Blob(x) => x.into(),
+            PropKind::Boolean(x) => x.into(),
+            PropKind::Bytes(x) => x.into(),
+            PropKind::CidLink(x) => x.into(),
+            PropKind::Integer(x) => x.into(),
+            PropKind::Null(x) => x.into(),
+            PropKind::Object(x) => x.into(),
+            PropKind::Ref(x) => x.into(),
+            PropKind::String(x) => x.into(),
+            PropKind::Union(x) => x.into(),
+            PropKind::Unknown(x) => x.into(),
+            PropKind::Generic(x) => x.into(),
         }
     }
 }
 
 impl Prop {
     pub fn from(src: &str, node: &Node) -> Result<Prop, ()> {
-        // TODO: handle optional properties
-        match node.kind() {
-            "property" => {
-                let name = node.named_child(0).unwrap().str(&src);
+        Prop::parse_with(src, node, &Aliases::default())
+    }
 
-                let value =
-                    PropKind::from(GenericProp::from(&src, &node.named_child(1).unwrap()).unwrap());
+    pub fn parse_with(src: &str, node: &Node, aliases: &Aliases) -> Result<Prop, ()> {
+        match node.kind() {
+            "property" | "optional" => {
+                let (Some(name), Some(typen)) = (
+                    node.child_by_field_name("name"),
+                    node.child_by_field_name("type"),
+                ) else {
+                    return Err(());
+                };
+                let value = PropKind::parse_with(src, &typen, aliases)?;
 
                 Ok(Prop {
-                    name,
+                    name: name.str(src),
                     value,
+                    optional: node.kind() == "optional",
                     loc: node.range(),
                 })
             }
@@ -59,24 +285,16 @@ impl Prop {
     }
 }
 
-pub fn parse_properties(src: &str, node: &Node) -> HashMap<String, Prop> {
-    let mut cursor = node.walk();
-    let params = node
-        .named_children(&mut cursor)
-        //.inspect(|x| panic!("{:?}", x.to_sexp()))
-        .map(|x| Prop::from(&src, &x).unwrap())
-        .map(|x| (x.name.clone(), x))
-        .collect();
-
-    params
-}
-
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct GenericProp {
     pub name: String,
     pub params: HashMap<Box<str>, Param>,
     pub slice: Slice,
+    // written with brackets, e.g. String[..10]
+    pub array: bool,
     pub loc: Range,
+    // params that couldn't be parsed, and unknown and mistyped ones once `expect` ran
+    pub diagnostics: Vec<Diagnostic>,
 }
 
 impl GenericProp {
@@ -87,18 +305,40 @@ impl GenericProp {
         match node.kind() {
             "type" => {
                 let mut cursor = node.walk();
-                let name = node.named_child(0).unwrap().str(&src);
-                let params = node
-                    .children_by_field_name("param", &mut cursor)
-                    .map(|x| Param::from(&src, &x).unwrap())
-                    .map(|x| (x.name.clone().into_boxed_str(), x));
-                let slice = Slice::from(&src, &node);
+                let Some(name) = node.named_child(0).map(|x| x.str(src)) else {
+                    return Err(());
+                };
+                let mut diagnostics = Vec::new();
+                let mut params = HashMap::new();
+                for param in node.children_by_field_name("param", &mut cursor) {
+                    match Param::from(src, &param) {
+                        // the first one wins
+                        Ok(x) if params.contains_key(x.name.as_str()) => diagnostics.push(
+                            Diagnostic::error(format!("`{}` is given twice", x.name), x.loc),
+                        ),
+                        Ok(x) => {
+                            params.insert(x.name.clone().into_boxed_str(), x);
+                        }
+                        Err(message) => diagnostics.push(Diagnostic::error(message, param.range())),
+                    }
+                }
+                let slice = Slice::parse(src, node).unwrap_or_else(|message| {
+                    diagnostics.push(Diagnostic::error(message, node.range()));
+                    Slice {
+                        loc: Some(node.range()),
+                        ..Slice::empty()
+                    }
+                });
+                let mut cursor = node.walk();
+                let array = node.children(&mut cursor).any(|x| x.kind() == "[");
 
                 Ok(GenericProp {
                     name,
-                    params: params.collect(),
+                    params,
                     slice,
+                    array,
                     loc: node.range(),
+                    diagnostics,
                 })
             }
             _ => Err(()),
@@ -106,6 +346,166 @@ impl GenericProp {
     }
 }
 
+// the kind of value a param takes, every type lists its params with these in a `PARAMS` table
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParamType {
+    String,
+    Integer,
+    Boolean,
+    Slice,
+    // a string format, quoted or bare, e.g. `did`
+    Format,
+    Ref,
+    List(&'static ParamType),
+    Either(&'static ParamType, &'static ParamType),
+}
+
+impl ParamType {
+    fn matches(&self, value: &ParamKind) -> bool {
+        match (self, value) {
+            (ParamType::String, ParamKind::String(_))
+            | (ParamType::Integer, ParamKind::Integer(_))
+            | (ParamType::Boolean, ParamKind::Boolean(_))
+            | (ParamType::Slice, ParamKind::Slice(_))
+            | (ParamType::Ref, ParamKind::Ref(_))
+            | (ParamType::Format, ParamKind::String(_) | ParamKind::Ident(_)) => true,
+            (ParamType::List(t), ParamKind::List(items)) => items.iter().all(|x| t.matches(x)),
+            (ParamType::Either(a, b), value) => a.matches(value) || b.matches(value),
+            _ => false,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            ParamType::String => "a string".to_string(),
+            ParamType::Integer => "an integer".to_string(),
+            ParamType::Boolean => "`true` or `false`".to_string(),
+            ParamType::Slice => "a range, e.g. `1..10`".to_string(),
+            ParamType::Format => "a format name, e.g. `did`".to_string(),
+            ParamType::Ref => "a ref, e.g. `#foo`".to_string(),
+            ParamType::List(t) => format!("a list of {}", t.plural()),
+            ParamType::Either(a, b) => format!("{} or {}", a.describe(), b.describe()),
+        }
+    }
+
+    fn plural(&self) -> String {
+        match self {
+            ParamType::String => "strings".to_string(),
+            ParamType::Integer => "integers".to_string(),
+            ParamType::Boolean => "booleans".to_string(),
+            ParamType::Slice => "ranges".to_string(),
+            ParamType::Format => "format names".to_string(),
+            ParamType::Ref => "refs".to_string(),
+            ParamType::List(t) => format!("lists of {}", t.plural()),
+            ParamType::Either(a, b) => format!("{} or {}", a.plural(), b.plural()),
+        }
+    }
+}
+
+// params every type takes on top of its own table
+const COMMON_PARAMS: &[(&str, ParamType)] = &[("description", ParamType::String)];
+
+impl GenericProp {
+    // checks the given params against the type's table, call before taking any of them
+    // unknown and mistyped params are reported and dropped, so the accessors below only see valid ones
+    pub fn expect(&mut self, accepted: &[(&str, ParamType)]) {
+        let mut given: Vec<Param> = self.params.drain().map(|(_, x)| x).collect();
+        given.sort_by_key(|x| x.loc.start_byte);
+        let accepted = || accepted.iter().chain(COMMON_PARAMS);
+        for param in given {
+            match accepted().find(|(name, _)| *name == param.name) {
+                None => {
+                    let mut message =
+                        format!("`{}` isn't a parameter of {}", param.name, self.name);
+                    if let Some(name) = suggest(&param.name, accepted().map(|(x, _)| *x)) {
+                        message.push_str(&format!(", did you mean `{name}`?"));
+                    }
+                    self.diagnostics.push(Diagnostic::error(message, param.loc));
+                }
+                Some((name, expected)) if !expected.matches(&param.value) => {
+                    let message = match (expected, &param.value) {
+                        (ParamType::Integer, ParamKind::Slice(_)) => {
+                            format!("`{name}` takes a single integer, not a range")
+                        }
+                        (ParamType::List(_), ParamKind::List(_)) => format!(
+                            "`{name}` expects {}, got a list with other values in it",
+                            expected.describe()
+                        ),
+                        (_, value) => format!(
+                            "`{name}` expects {}, got {}",
+                            expected.describe(),
+                            value.describe()
+                        ),
+                    };
+                    self.diagnostics.push(Diagnostic::error(message, param.loc));
+                }
+                Some((name, ParamType::Format)) => match &param.value {
+                    ParamKind::String(format) | ParamKind::Ident(format)
+                        if StringFormats::from_str(format).is_none() =>
+                    {
+                        let mut message = format!("unknown format `{format}`");
+                        if let Some(name) = suggest(format, StringFormats::NAMES.iter().copied()) {
+                            message.push_str(&format!(", did you mean `{name}`?"));
+                        }
+                        self.diagnostics.push(Diagnostic::error(message, param.loc));
+                    }
+                    _ => {
+                        self.params.insert((*name).into(), param);
+                    }
+                },
+                Some((name, _)) => {
+                    self.params.insert((*name).into(), param);
+                }
+            }
+        }
+    }
+
+    // takes a param out, every param can only be taken once
+    pub fn take<T>(&mut self, name: &str, f: impl Fn(&ParamKind) -> Option<T>) -> Option<T> {
+        self.params.remove(name).and_then(|x| f(&x.value))
+    }
+
+    pub fn slice(&mut self, name: &str) -> Option<Slice> {
+        self.take(name, |x| match x {
+            ParamKind::Slice(s) => Some(*s),
+            _ => None,
+        })
+    }
+
+    pub fn integer(&mut self, name: &str) -> Option<i64> {
+        self.take(name, |x| match x {
+            ParamKind::Integer(i) => Some(*i),
+            _ => None,
+        })
+    }
+
+    pub fn string(&mut self, name: &str) -> Option<String> {
+        self.take(name, |x| match x {
+            ParamKind::String(s) => Some(s.clone()),
+            _ => None,
+        })
+    }
+
+    pub fn boolean(&mut self, name: &str) -> Option<bool> {
+        self.take(name, |x| match x {
+            ParamKind::Boolean(b) => Some(*b),
+            _ => None,
+        })
+    }
+
+    pub fn list<T>(&mut self, name: &str, f: impl Fn(&ParamKind) -> Option<T>) -> Option<Vec<T>> {
+        self.take(name, |x| match x {
+            ParamKind::List(items) => Some(items.iter().filter_map(&f).collect()),
+            _ => None,
+        })
+    }
+
+    // the diagnostics from `expect`, once the type has taken everything it needs
+    pub fn finish(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use tree_sitter::{Parser, Tree};
@@ -154,9 +554,106 @@ mod tests {
             assert!(s.graphemes.start == Some(2));
             assert!(s.graphemes.end == Some(4));
             assert!(s.format == Some(lexicon::StringFormats::Did));
-            // assert!(s.default == Some("this is not a valid did lol".to_string())); default not parsed yet
+            assert!(s.default == Some("this is not a valid did lol".to_string()));
         } else {
             panic!("Expected string type");
         }
     }
+
+    #[test]
+    fn unknown_and_mistyped_params() {
+        let src = "@@[ foo: Integer(lenn=5, range=5, defualt=1) ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let prop = Prop::from(src, &node).unwrap();
+        let messages: Vec<String> = prop.value.check().into_iter().map(|x| x.message).collect();
+        assert!(
+            messages
+                == vec![
+                    "`lenn` isn't a parameter of Integer",
+                    "`range` expects a range, e.g. `1..10`, got an integer",
+                    "`defualt` isn't a parameter of Integer, did you mean `default`?",
+                ]
+        );
+    }
+
+    #[test]
+    fn repeated_params() {
+        let src = "@@[ foo: String(len=..5, len=..10) ]@@";
+        let tree = parse(src);
+        let node = unwrap_harness(&tree);
+        let prop = Prop::from(src, &node).unwrap();
+        let PropKind::String(string) = &prop.value else {
+            panic!("Expected string type");
+        };
+        assert!(string.length.end == Some(5));
+        let messages: Vec<String> = prop.value.check().into_iter().map(|x| x.message).collect();
+        assert!(messages == vec!["`len` is given twice"]);
+    }
+
+    #[test]
+    fn format_and_range_params() {
+        let src = "@@[ foo: String(format=\"dyd\") ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let prop = Prop::from(src, &node).unwrap();
+        let messages: Vec<String> = prop.value.check().into_iter().map(|x| x.message).collect();
+        assert!(messages == vec!["unknown format `dyd`, did you mean `did`?"]);
+
+        let src = "@@[ foo: Blob(size=1..2) ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let prop = Prop::from(src, &node).unwrap();
+        let messages: Vec<String> = prop.value.check().into_iter().map(|x| x.message).collect();
+        assert!(messages == vec!["`size` takes a single integer, not a range"]);
+    }
+
+    #[test]
+    fn every_field_type() {
+        let src = "@@[ foo: Union(refs=[#a, #b]) ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let prop = Prop::from(src, &node).unwrap();
+        assert!(matches!(prop.value, PropKind::Union(_)));
+
+        let src = "@@[ foo: #image ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let prop = Prop::from(src, &node).unwrap();
+        assert!(matches!(prop.value, PropKind::Ref(_)));
+
+        let src = "@@[ foo: Did[..5] ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let prop = Prop::from(src, &node).unwrap();
+        let PropKind::Array(array) = prop.value else {
+            panic!("Expected array type");
+        };
+        assert!(
+            matches!(*array.items, PropKind::String(ref s) if s.format == Some(lexicon::StringFormats::Did))
+        );
+    }
+
+    #[test]
+    fn ref_arrays() {
+        let src = "@@[ feed: #post[..50] ]@@";
+        let tree = parse(src);
+        let node = unwrap_harness(&tree);
+        let prop = Prop::from(src, &node).unwrap();
+        let PropKind::Array(array) = prop.value else {
+            panic!("expected an array");
+        };
+        assert!(array.length.end == Some(50));
+        assert!(matches!(*array.items, PropKind::Ref(ref r) if r.reference == "#post"));
+    }
+
+    #[test]
+    fn unknown_type() {
+        let src = "@@[ foo: Strin ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let prop = Prop::from(src, &node).unwrap();
+        let messages: Vec<String> = prop.value.check().into_iter().map(|x| x.message).collect();
+        assert!(messages == vec!["unknown type `Strin`, did you mean `String`?"]);
+    }
 }
@@ -0,0 +1,115 @@
+use lexicon::{AtpArray, AtpTypes};
+use tree_sitter::Range;
+
+use crate::{
+    Slice,
+    diagnostic::Diagnostic,
+    props::{GenericProp, ParamType, PropKind},
+};
+
+#[derive(Debug, PartialEq)]
+pub struct Type {
+    pub items: Box<PropKind>,
+    pub length: Slice,
+    pub description: Option<String>,
+    pub loc: Range,
+}
+
+pub const PARAMS: &[(&str, ParamType)] = &[];
+
+impl Type {
+    // e.g. String[..10], the brackets have already been taken off `items`
+    pub fn of(items: PropKind, length: Slice, loc: Range) -> Type {
+        Type {
+            items: Box::new(items),
+            length,
+            description: None,
+            loc,
+        }
+    }
+
+    pub fn check(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.items.check();
+        if !self.length.fits_u32() {
+            diagnostics.push(Diagnostic::error(
+                format!(
+                    "array length {} has to be between 0 and {}",
+                    self.length,
+                    u32::MAX
+                ),
+                self.loc,
+            ));
+        }
+        diagnostics
+    }
+}
+
+// a bare `Array` holds anything
+impl From<GenericProp> for Type {
+    fn from(mut t: GenericProp) -> Self {
+        t.expect(PARAMS);
+        let description = t.string("description");
+        let items = PropKind::Unknown(super::unknown::Type {
+            description: None,
+            diagnostics: t.finish(),
+            loc: t.loc,
+        });
+        Type {
+            description,
+            ..Type::of(items, t.slice, t.loc)
+        }
+    }
+}
+
+impl Into<AtpArray> for Type {
+    fn into(self) -> AtpArray {
+        AtpArray {
+            description: self.description,
+            items: Box::new((*self.items).into()),
+            min_length: self.length.start.and_then(|x| x.try_into().ok()),
+            max_length: self.length.end.and_then(|x| x.try_into().ok()),
+        }
+    }
+}
+
+impl Into<AtpTypes> for Type {
+    fn into(self) -> AtpTypes {
+        AtpTypes::Array(self.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tree_sitter::{Node, Parser, Tree};
+
+    use super::*;
+
+    fn unwrap_harness(tree: &Tree) -> Node {
+        tree.root_node().child(1).unwrap()
+    }
+
+    fn parse(src: &str) -> Tree {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_ana::LANGUAGE.into())
+            .expect("error loading ana grammar");
+        parser.parse(src, None).unwrap()
+    }
+
+    #[test]
+    fn items() {
+        let src = "@@[ String(len=..640)[..10] ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let generic_type = GenericProp::from(src, &node).unwrap();
+        let PropKind::Array(array_type) = PropKind::from(generic_type) else {
+            panic!("Expected array type");
+        };
+        assert!(array_type.length.end == Some(10));
+        if let PropKind::String(s) = *array_type.items {
+            assert!(s.length.end == Some(640));
+        } else {
+            panic!("Expected string items");
+        }
+    }
+}
@@ -1,41 +1,78 @@
 use lexicon::{AtpBlob, AtpTypes};
 use tree_sitter::Range;
 
-use crate::{ParamKind, props::GenericProp};
+use crate::{
+    ParamKind,
+    diagnostic::Diagnostic,
+    props::{GenericProp, ParamType},
+};
 
 #[derive(Debug, PartialEq)]
 pub struct Type {
     pub accept: Option<Vec<String>>,
-    pub size: Option<i32>,
+    pub size: Option<i64>,
+    pub description: Option<String>,
+    // unknown and mistyped params
+    pub diagnostics: Vec<Diagnostic>,
     pub loc: Range,
 }
 
-impl From<GenericProp> for Type {
-    fn from(t: GenericProp) -> Self {
-        let accept = t.params.get("accept").map_or(None, |x| {
-            match &x.value {
-                ParamKind::String(x) => Some(vec![x.clone()]), // todo: scalar supports
-                _ => None,
-            }
-        });
+pub const PARAMS: &[(&str, ParamType)] = &[
+    // a single mime type or a list of them
+    (
+        "accept",
+        ParamType::Either(&ParamType::String, &ParamType::List(&ParamType::String)),
+    ),
+    ("size", ParamType::Integer),
+];
 
-        let size = t.params.get("size").map_or(None, |x| match x.value {
-            ParamKind::Integer(x) => Some(x),
+impl From<GenericProp> for Type {
+    fn from(mut t: GenericProp) -> Self {
+        t.expect(PARAMS);
+        let accept = t.take("accept", |x| match x {
+            ParamKind::String(s) => Some(vec![s.clone()]),
+            ParamKind::List(items) => Some(
+                items
+                    .iter()
+                    .filter_map(|x| match x {
+                        ParamKind::String(s) => Some(s.clone()),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
             _ => None,
         });
+        let size = t.integer("size");
 
         Type {
             accept,
             size,
+            description: t.string("description"),
+            diagnostics: t.finish(),
             loc: t.loc,
         }
     }
 }
 
+impl Type {
+    pub fn check(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.diagnostics.clone();
+        if let Some(size) = self.size
+            && u32::try_from(size).is_err()
+        {
+            diagnostics.push(Diagnostic::error(
+                format!("blob size has to be between 0 and {} bytes", u32::MAX),
+                self.loc,
+            ));
+        }
+        diagnostics
+    }
+}
+
 impl Into<AtpBlob> for Type {
     fn into(self) -> AtpBlob {
         AtpBlob {
-            description: None,
+            description: self.description,
             accept: self.accept,
             max_size: self.size.and_then(|x| x.try_into().ok()),
         }
@@ -95,4 +132,15 @@ mod tests {
         let blob_type = Type::from(generic_type);
         assert!(blob_type.accept == Some(vec!["image/jxl".to_string()]));
     }
+
+    #[test]
+    fn accept_list() {
+        let src = "@@[ Blob(accept=[\"image/png\", \"image/jpeg\"]) ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let generic_type = GenericProp::from(src, &node).unwrap();
+        let blob_type = Type::from(generic_type);
+        assert!(blob_type.accept == Some(vec!["image/png".to_string(), "image/jpeg".to_string()]));
+        assert!(blob_type.check().is_empty());
+    }
 }
@@ -1,29 +1,60 @@
 use lexicon::{AtpBoolean, AtpTypes};
 use tree_sitter::Range;
 
-use crate::props::GenericProp;
+use crate::{
+    diagnostic::Diagnostic,
+    props::{GenericProp, ParamType},
+};
 
 #[derive(Debug, PartialEq)]
 pub struct Type {
     pub default: Option<bool>,
+    pub constant: Option<bool>,
+    pub description: Option<String>,
+    // unknown and mistyped params
+    pub diagnostics: Vec<Diagnostic>,
     pub loc: Range,
 }
 
+pub const PARAMS: &[(&str, ParamType)] = &[
+    ("default", ParamType::Boolean),
+    ("const", ParamType::Boolean),
+];
+
 impl From<GenericProp> for Type {
-    fn from(t: GenericProp) -> Self {
+    fn from(mut t: GenericProp) -> Self {
+        t.expect(PARAMS);
         Type {
-            default: None,
+            default: t.boolean("default"),
+            constant: t.boolean("const"),
+            description: t.string("description"),
+            diagnostics: t.finish(),
             loc: t.loc,
         }
     }
 }
 
+impl Type {
+    pub fn check(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.diagnostics.clone();
+        if let (Some(default), Some(constant)) = (self.default, self.constant)
+            && default != constant
+        {
+            diagnostics.push(Diagnostic::error(
+                format!("default {default} can't be used, the value is always {constant}"),
+                self.loc,
+            ));
+        }
+        diagnostics
+    }
+}
+
 impl Into<AtpBoolean> for Type {
     fn into(self) -> AtpBoolean {
         AtpBoolean {
-            description: None,
-            constant: None,
-            default: None,
+            description: self.description,
+            constant: self.constant,
+            default: self.default,
         }
     }
 }
@@ -64,14 +95,24 @@ mod tests {
     }
 
     #[test]
-    #[ignore] // TODO: implement bool primative in grammar
     fn default_value() {
-        let src = "@@[ Boolean(default=True) ]@@";
+        let src = "@@[ Boolean(default=true) ]@@";
         let tree = parse(&src);
         let node = unwrap_harness(&tree);
         let generic_type = GenericProp::from(src, &node).unwrap();
         let boolean_type = Type::from(generic_type);
-        assert!(boolean_type.loc.start_byte == 4);
-        assert!(boolean_type.loc.end_byte == 8);
+        assert!(boolean_type.default == Some(true));
+        assert!(boolean_type.constant == None);
+    }
+
+    #[test]
+    fn check() {
+        let src = "@@[ Boolean(default=true, const=false) ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let generic_type = GenericProp::from(src, &node).unwrap();
+        let boolean_type = Type::from(generic_type);
+        assert!(boolean_type.constant == Some(false));
+        assert!(boolean_type.check().len() == 1);
     }
 }
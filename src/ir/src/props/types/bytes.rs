@@ -1,32 +1,54 @@
 use lexicon::{AtpBytes, AtpTypes};
 use tree_sitter::Range;
 
-use crate::{ParamKind, Slice, props::GenericProp};
+use crate::{
+    Slice,
+    diagnostic::Diagnostic,
+    props::{GenericProp, ParamType},
+};
 
 #[derive(Debug, PartialEq)]
 pub struct Type {
     pub size: Slice,
+    pub description: Option<String>,
+    // unknown and mistyped params
+    pub diagnostics: Vec<Diagnostic>,
     pub loc: Range,
 }
 
+pub const PARAMS: &[(&str, ParamType)] = &[("size", ParamType::Slice)];
+
 impl From<GenericProp> for Type {
-    fn from(t: GenericProp) -> Self {
-        let size = t
-            .params
-            .get("size")
-            .map_or(Slice::empty(), |x| match x.value {
-                ParamKind::Slice(s) => s,
-                _ => Slice::empty(),
-            });
+    fn from(mut t: GenericProp) -> Self {
+        t.expect(PARAMS);
+        let size = t.slice("size").unwrap_or(Slice::empty());
+
+        Type {
+            size,
+            description: t.string("description"),
+            diagnostics: t.finish(),
+            loc: t.loc,
+        }
+    }
+}
 
-        Type { size, loc: t.loc }
+impl Type {
+    pub fn check(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.diagnostics.clone();
+        if !self.size.fits_u32() {
+            diagnostics.push(Diagnostic::error(
+                format!("size={} has to be between 0 and {}", self.size, u32::MAX),
+                self.loc,
+            ));
+        }
+        diagnostics
     }
 }
 
 impl Into<AtpBytes> for Type {
     fn into(self) -> AtpBytes {
         AtpBytes {
-            description: None,
+            description: self.description,
             min_length: self.size.start.and_then(|x| x.try_into().ok()),
             max_length: self.size.end.and_then(|x| x.try_into().ok()),
         }
@@ -77,4 +99,17 @@ mod tests {
         assert!(integer_type.size.start == Some(4096));
         assert!(integer_type.size.end == Some(8192));
     }
+    #[test]
+    fn size_out_of_range() {
+        let src = "@@[ Bytes(size=..4294967296) ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let generic_type = GenericProp::from(src, &node).unwrap();
+        let messages: Vec<String> = Type::from(generic_type)
+            .check()
+            .into_iter()
+            .map(|x| x.message)
+            .collect();
+        assert!(messages == vec!["size=..4294967296 has to be between 0 and 4294967295"]);
+    }
 }
@@ -0,0 +1,331 @@
+use lexicon::{AtpRef, AtpTypes};
+use tree_sitter::{Node, Range};
+
+use crate::{
+    NodeHelpers, ParamKind,
+    diagnostic::Diagnostic,
+    props::{
+        GenericProp, PropKind,
+        alias::{Aliases, Target},
+        is_builtin,
+        types::{array, object, reference, unknown},
+    },
+};
+
+// `Page<T> { cursor?: String; items: T[..100] }`, a def that's only written out where it's used
+#[derive(Debug, Clone)]
+pub struct Template<'t> {
+    pub name: String,
+    pub params: Vec<String>,
+    pub body: Node<'t>,
+    pub loc: Range,
+}
+
+impl<'t> Template<'t> {
+    pub fn from(src: &str, node: &Node<'t>) -> Result<Template<'t>, String> {
+        let (Some(name), Some(body)) = (
+            node.child_by_field_name("name"),
+            node.child_by_field_name("body"),
+        ) else {
+            return Err("generic defs are written `Page<T> { items: T[..100] }`".to_string());
+        };
+        let mut params: Vec<String> = Vec::new();
+        let mut cursor = node.walk();
+        for param in node.children_by_field_name("generic", &mut cursor) {
+            let param = param.str(src);
+            if is_builtin(&param) {
+                return Err(format!(
+                    "`{param}` is a built-in type, it can't be a type param"
+                ));
+            }
+            if params.contains(&param) {
+                return Err(format!("type param `{param}` is listed twice"));
+            }
+            params.push(param);
+        }
+        Ok(Template {
+            name: name.str(src),
+            params,
+            body,
+            loc: node.range(),
+        })
+    }
+}
+
+// a generic def filled in with its type arguments, e.g. `Page<#post>`
+// the compiler turns it into a def named after the arguments, `pagePost`, and refers to that
+#[derive(Debug, PartialEq)]
+pub struct Type {
+    pub name: String,
+    // the arguments with aliases written out, e.g. `Page<#post>`, uses with the same key are the same def
+    pub key: String,
+    pub object: object::Type,
+    pub diagnostics: Vec<Diagnostic>,
+    pub loc: Range,
+}
+
+impl Type {
+    pub fn check(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.diagnostics.clone();
+        diagnostics.extend(self.object.check());
+        diagnostics
+    }
+}
+
+// only the ref, the def it points at is up to whoever lowers the object
+impl Into<AtpTypes> for Type {
+    fn into(self) -> AtpTypes {
+        AtpTypes::Ref(AtpRef {
+            description: None,
+            reference: format!("#{}", self.name),
+        })
+    }
+}
+
+// `prop` is the use site, e.g. `Page<#post>[..10]`, the brackets make an array of the instance
+pub fn instantiate(
+    src: &str,
+    node: &Node,
+    template: &Template,
+    aliases: &Aliases,
+    mut prop: GenericProp,
+) -> PropKind {
+    let mut cursor = node.walk();
+    let arguments: Vec<Node> = node
+        .children_by_field_name("argument", &mut cursor)
+        .collect();
+    if arguments.len() != template.params.len() {
+        let message = format!(
+            "`{}` takes {} type argument{}, got {}",
+            template.name,
+            template.params.len(),
+            if template.params.len() == 1 { "" } else { "s" },
+            arguments.len()
+        );
+        return fail(prop, message);
+    }
+    if !prop.params.is_empty() {
+        let message = format!(
+            "`{}` doesn't take params, only type arguments",
+            template.name
+        );
+        return fail(prop, message);
+    }
+    if aliases.instantiating.contains(&template.name) {
+        let message = format!("`{}` uses itself, it would never end", template.name);
+        return fail(prop, message);
+    }
+
+    // the body sees the params on top of everything the use site sees
+    let mut scope = aliases.clone();
+    scope.instantiating.push(template.name.clone());
+    let mut canonicals = Vec::new();
+    for (param, argument) in template.params.iter().zip(&arguments) {
+        if argument.child_by_field_name("argument").is_some() {
+            let message = "generic defs can't be type arguments yet, wrap it in a def";
+            return fail(prop, message.to_string());
+        }
+        let target = match argument.kind() {
+            "ref" => reference::Type::from(src, argument).map(Target::Ref),
+            _ => GenericProp::from(src, argument).map(|x| resolve(aliases.expand(x), aliases)),
+        };
+        let Ok(target) = target else {
+            let message = format!("`{}` isn't a type", argument.str(src));
+            return fail(prop, message);
+        };
+        canonicals.push(canonical(&target));
+        scope.bind(param, target, argument.range());
+    }
+
+    let name = instance_name(&template.name, &canonicals);
+    let key = format!("{}<{}>", template.name, canonicals.join(", "));
+    let mut object = object::Type::parse(src, &template.body, &scope);
+    object.loc = prop.loc;
+    let instance = PropKind::Generic(Type {
+        name,
+        key,
+        object,
+        diagnostics: std::mem::take(&mut prop.diagnostics),
+        loc: prop.loc,
+    });
+    if !prop.array {
+        return instance;
+    }
+    PropKind::Array(array::Type::of(instance, prop.slice, prop.loc))
+}
+
+fn fail(mut prop: GenericProp, message: String) -> PropKind {
+    prop.diagnostics.push(Diagnostic::error(message, prop.loc));
+    PropKind::Unknown(unknown::Type {
+        description: None,
+        diagnostics: prop.diagnostics,
+        loc: prop.loc,
+    })
+}
+
+// an alias that ended up at a ref, or a param that was given one, is that ref
+fn resolve(prop: GenericProp, aliases: &Aliases) -> Target {
+    match aliases.reference(&prop.name) {
+        Some(reference) if prop.params.is_empty() && !prop.array => Target::Ref(reference.clone()),
+        _ => Target::Type(prop),
+    }
+}
+
+// an argument written out the same way however it was spelled, e.g. `String(len=..10)` or `#post`
+fn canonical(target: &Target) -> String {
+    let prop = match target {
+        Target::Ref(x) => return x.reference.clone(),
+        Target::Type(x) => x,
+    };
+    let mut params: Vec<String> = prop
+        .params
+        .values()
+        .map(|x| format!("{}={}", x.name, value(&x.value)))
+        .collect();
+    params.sort();
+    let mut out = prop.name.clone();
+    if !params.is_empty() {
+        out.push_str(&format!("({})", params.join(", ")));
+    }
+    if prop.array {
+        out.push_str(&format!("[{}]", prop.slice));
+    }
+    out
+}
+
+fn value(kind: &ParamKind) -> String {
+    match kind {
+        ParamKind::String(x) => format!("{x:?}"),
+        ParamKind::Integer(x) => x.to_string(),
+        ParamKind::Boolean(x) => x.to_string(),
+        ParamKind::Slice(x) => x.to_string(),
+        ParamKind::List(x) => {
+            let values: Vec<String> = x.iter().map(value).collect();
+            format!("[{}]", values.join(", "))
+        }
+        ParamKind::Ref(x) | ParamKind::Ident(x) => x.clone(),
+    }
+}
+
+// a canonical argument as it goes into an instance name, `com.example.feed#post` is `ComExampleFeedPost`
+// ranges keep their direction, `len=..10` is `LenTo10` and `len=10..` is `Len10To`
+fn spell(canonical: &str) -> String {
+    canonical
+        .replace("..", " to ")
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .map(capitalize)
+        .collect()
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+// `Page<#post>` is `pagePost`, the same arguments always give the same def
+// different ones can still end up with the same name, the compiler reports those
+fn instance_name(template: &str, canonicals: &[String]) -> String {
+    let mut chars = template.chars();
+    let mut name = match chars.next() {
+        Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+        None => String::new(),
+    };
+    for canonical in canonicals {
+        name.push_str(&spell(canonical));
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use tree_sitter::{Parser, Tree};
+
+    use super::*;
+    use crate::props::alias::Alias;
+
+    fn unwrap_harness(tree: &Tree) -> Node {
+        tree.root_node().child(1).unwrap()
+    }
+
+    fn parse(src: &str) -> Tree {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_ana::LANGUAGE.into())
+            .expect("error loading ana grammar");
+        parser.parse(src, None).unwrap()
+    }
+
+    // the def and the use site share a source, templates point into it
+    fn instance(def: &str, src: &str) -> PropKind {
+        let src = format!("@@[ {def} ]@@ @@[ {src} ]@@");
+        let tree = parse(&src);
+        let template = Template::from(&src, &unwrap_harness(&tree)).unwrap();
+        let mut aliases = Aliases::default();
+        aliases.insert_template(template).unwrap();
+        let node = tree.root_node().child(4).unwrap();
+        PropKind::parse_with(&src, &node, &aliases).unwrap()
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(instance_name("Page", &["#post".to_string()]), "pagePost");
+        assert_eq!(spell("com.example.feed#post"), "ComExampleFeedPost");
+        assert_eq!(spell("String[..10]"), "StringTo10");
+        assert_eq!(spell("String(len=..10)"), "StringLenTo10");
+        assert_eq!(spell("String(len=10..)"), "StringLen10To");
+    }
+
+    #[test]
+    fn fills_in() {
+        let kind = instance(
+            "Page<T> { cursor?: String; items: T[..100] }",
+            "Page<#post>",
+        );
+        let PropKind::Generic(page) = kind else {
+            panic!("expected an instance, got {kind:?}");
+        };
+        assert!(page.name == "pagePost");
+        assert!(page.check().is_empty());
+        let PropKind::Array(items) = &page.object.props["items"].value else {
+            panic!("expected an array");
+        };
+        let PropKind::Ref(post) = items.items.as_ref() else {
+            panic!("expected a ref");
+        };
+        assert!(post.reference == "#post");
+        assert!(items.length.end == Some(100));
+    }
+
+    #[test]
+    fn aliases_are_written_out() {
+        let src =
+            "@@[ Page<T> { items: T[..100] } ]@@ @@[ type Post = #post; ]@@ @@[ Page<Post> ]@@";
+        let tree = parse(src);
+        let mut aliases = Aliases::default();
+        let template = Template::from(src, &unwrap_harness(&tree)).unwrap();
+        aliases.insert_template(template).unwrap();
+        let alias = Alias::from(src, &tree.root_node().child(4).unwrap()).unwrap();
+        aliases.insert(alias).unwrap();
+        let node = tree.root_node().child(7).unwrap();
+        let kind = PropKind::parse_with(src, &node, &aliases).unwrap();
+        let PropKind::Generic(page) = kind else {
+            panic!("expected an instance, got {kind:?}");
+        };
+        // the same def as `Page<#post>`
+        assert!(page.name == "pagePost");
+        assert!(page.key == "Page<#post>");
+    }
+
+    #[test]
+    fn arity() {
+        let kind = instance("Pair<A, B> { a: A; b: B }", "Pair<String>");
+        let PropKind::Unknown(unknown) = kind else {
+            panic!("expected an error, got {kind:?}");
+        };
+        assert!(unknown.diagnostics[0].message == "`Pair` takes 2 type arguments, got 1");
+    }
+}
@@ -1,46 +1,124 @@
-use lexicon::{AtpInteger, AtpTypes};
+use lexicon::{AtpInteger, AtpTypes, MAX_SAFE_INTEGER};
 use tree_sitter::Range;
 
-use crate::{ParamKind, Slice, props::GenericProp};
+use crate::{
+    ParamKind, Slice,
+    diagnostic::Diagnostic,
+    props::{GenericProp, ParamType},
+};
 
 #[derive(Debug, PartialEq)]
 pub struct Type {
     pub range: Slice,
-    pub default: Option<i32>,
+    pub enumeration: Option<Vec<i64>>,
+    pub default: Option<i64>,
+    pub constant: Option<i64>,
+    pub description: Option<String>,
+    // unknown and mistyped params
+    pub diagnostics: Vec<Diagnostic>,
     pub loc: Range,
 }
 
+pub const PARAMS: &[(&str, ParamType)] = &[
+    ("range", ParamType::Slice),
+    ("enum", ParamType::List(&ParamType::Integer)),
+    ("default", ParamType::Integer),
+    ("const", ParamType::Integer),
+];
+
 impl From<GenericProp> for Type {
-    fn from(t: GenericProp) -> Self {
-        let range = t
-            .params
-            .get("range")
-            .map_or(Slice::empty(), |x| match x.value {
-                ParamKind::Slice(s) => s,
-                _ => Slice::empty(),
-            });
-        let default = t.params.get("default").map_or(None, |x| match x.value {
-            ParamKind::Integer(i) => Some(i),
+    fn from(mut t: GenericProp) -> Self {
+        t.expect(PARAMS);
+        let range = t.slice("range").unwrap_or(Slice::empty());
+        let enumeration = t.list("enum", |x| match x {
+            ParamKind::Integer(i) => Some(*i),
             _ => None,
         });
+        let default = t.integer("default");
+        let constant = t.integer("const");
 
         Type {
+            enumeration,
             default,
+            constant,
             range,
+            description: t.string("description"),
+            diagnostics: t.finish(),
             loc: t.loc,
         }
     }
 }
 
+impl Type {
+    // default, const and the enum values all have to be within range
+    // and everything has to survive a round trip through a javascript number
+    pub fn check(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.diagnostics.clone();
+        let values = [
+            self.range.start,
+            self.range.end,
+            self.default,
+            self.constant,
+        ];
+        let unsafe_value = values
+            .into_iter()
+            .flatten()
+            .chain(self.enumeration.iter().flatten().copied())
+            .find(|x| !(-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(x));
+        if let Some(value) = unsafe_value {
+            diagnostics.push(Diagnostic::error(
+                format!("{value} is outside of the safe integer range, ±2^53-1"),
+                self.loc,
+            ));
+        }
+        for (param, value) in [("default", self.default), ("const", self.constant)] {
+            let Some(value) = value else {
+                continue;
+            };
+            if let Some(enumeration) = &self.enumeration
+                && !enumeration.contains(&value)
+            {
+                diagnostics.push(Diagnostic::error(
+                    format!("{param} {value} isn't one of the enum values"),
+                    self.loc,
+                ));
+            }
+            if !self.range.contains(value) {
+                diagnostics.push(Diagnostic::error(
+                    format!("{param} {value} is outside of range={}", self.range),
+                    self.loc,
+                ));
+            }
+        }
+        for value in self.enumeration.iter().flatten() {
+            if !self.range.contains(*value) {
+                diagnostics.push(Diagnostic::error(
+                    format!("enum value {value} is outside of range={}", self.range),
+                    self.loc,
+                ));
+            }
+        }
+        if let (Some(default), Some(constant)) = (self.default, self.constant)
+            && default != constant
+        {
+            diagnostics.push(Diagnostic::error(
+                format!("default {default} can't be used, the value is always {constant}"),
+                self.loc,
+            ));
+        }
+        diagnostics
+    }
+}
+
 impl Into<AtpInteger> for Type {
     fn into(self) -> AtpInteger {
         AtpInteger {
-            description: None,
-            constant: None,
+            description: self.description,
+            constant: self.constant,
             default: self.default,
             minimum: self.range.start,
             maximum: self.range.end,
-            enumeration: None, // todo
+            enumeration: self.enumeration,
         }
     }
 }
@@ -104,4 +182,70 @@ mod tests {
         assert!(integer_type.range.start == Some(42));
         assert!(integer_type.range.end == Some(69));
     }
+
+    #[test]
+    fn large_values() {
+        let src = "@@[ Integer(range=0..9007199254740991, default=4294967296) ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let generic_type = GenericProp::from(src, &node).unwrap();
+        let integer_type = Type::from(generic_type);
+        assert!(integer_type.default == Some(4294967296));
+        assert!(integer_type.check().is_empty());
+
+        let src = "@@[ Integer(range=..9007199254740992) ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let generic_type = GenericProp::from(src, &node).unwrap();
+        let messages: Vec<String> = Type::from(generic_type)
+            .check()
+            .into_iter()
+            .map(|x| x.message)
+            .collect();
+        assert!(messages == vec!["9007199254740992 is outside of the safe integer range, ±2^53-1"]);
+    }
+
+    #[test]
+    fn negative_range() {
+        let src = "@@[ Integer(range=-5..5, default=-1) ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let generic_type = GenericProp::from(src, &node).unwrap();
+        let integer_type = Type::from(generic_type);
+        assert!(integer_type.range.start == Some(-5));
+        assert!(integer_type.default == Some(-1));
+        assert!(integer_type.check().is_empty());
+    }
+
+    #[test]
+    fn enum_and_const() {
+        let src = "@@[ Integer(enum=[1, 2, 3], const=2) ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let generic_type = GenericProp::from(src, &node).unwrap();
+        let integer_type = Type::from(generic_type);
+        assert!(integer_type.enumeration == Some(vec![1, 2, 3]));
+        assert!(integer_type.constant == Some(2));
+        assert!(integer_type.check().is_empty());
+    }
+
+    #[test]
+    fn check() {
+        let src = "@@[ Integer(range=1..10, enum=[5, 20], default=7) ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let generic_type = GenericProp::from(src, &node).unwrap();
+        let messages: Vec<String> = Type::from(generic_type)
+            .check()
+            .into_iter()
+            .map(|x| x.message)
+            .collect();
+        assert!(
+            messages
+                == vec![
+                    "default 7 isn't one of the enum values",
+                    "enum value 20 is outside of range=1..10",
+                ]
+        );
+    }
 }
@@ -1,18 +1,13 @@
+pub mod array;
 pub mod blob;
 pub mod boolean;
 pub mod bytes;
 pub mod cid_link;
+pub mod generic;
 pub mod integer;
 pub mod null;
+pub mod object;
+pub mod reference;
 pub mod string;
-
-#[derive(Debug, PartialEq)]
-pub enum Prop {
-    Blob(blob::Type),
-    Boolean(boolean::Type),
-    Bytes(bytes::Type),
-    CidLink(cid_link::Type),
-    Integer(integer::Type),
-    Null(null::Type),
-    String(string::Type),
-}
+pub mod union;
+pub mod unknown;
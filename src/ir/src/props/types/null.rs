@@ -1,22 +1,42 @@
 use lexicon::{AtpNull, AtpTypes};
 use tree_sitter::Range;
 
-use crate::props::GenericProp;
+use crate::{
+    diagnostic::Diagnostic,
+    props::{GenericProp, ParamType},
+};
 
 #[derive(Debug, PartialEq)]
 pub struct Type {
+    pub description: Option<String>,
+    pub diagnostics: Vec<Diagnostic>,
     pub loc: Range,
 }
 
+pub const PARAMS: &[(&str, ParamType)] = &[];
+
 impl From<GenericProp> for Type {
-    fn from(t: GenericProp) -> Self {
-        Type { loc: t.loc }
+    fn from(mut t: GenericProp) -> Self {
+        t.expect(PARAMS);
+        Type {
+            description: t.string("description"),
+            diagnostics: t.finish(),
+            loc: t.loc,
+        }
+    }
+}
+
+impl Type {
+    pub fn check(&self) -> Vec<Diagnostic> {
+        self.diagnostics.clone()
     }
 }
 
 impl Into<AtpNull> for Type {
     fn into(self) -> AtpNull {
-        AtpNull { description: None }
+        AtpNull {
+            description: self.description,
+        }
     }
 }
 
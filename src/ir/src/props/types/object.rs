@@ -0,0 +1,174 @@
+use lexicon::{AtpObject, AtpTypes, IndexMap};
+use tree_sitter::{Node, Range};
+
+use crate::{
+    diagnostic::Diagnostic,
+    props::{GenericProp, ParamType, Prop, alias::Aliases, types::reference},
+};
+
+// an object used as a field type rather than declared as a def, either a bare `Object`
+// or written out inline, e.g. `image: { blob: Blob; alt?: String }`
+#[derive(Debug, PartialEq)]
+pub struct Type {
+    pub props: IndexMap<String, Prop>,
+    // `...#common`, with the number of props that came before each one
+    pub spreads: Vec<(String, usize, Range)>,
+    pub description: Option<String>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub loc: Range,
+}
+
+pub const PARAMS: &[(&str, ParamType)] = &[];
+
+impl From<GenericProp> for Type {
+    fn from(mut t: GenericProp) -> Self {
+        t.expect(PARAMS);
+        Type {
+            props: IndexMap::new(),
+            spreads: Vec::new(),
+            description: t.string("description"),
+            diagnostics: t.finish(),
+            loc: t.loc,
+        }
+    }
+}
+
+impl Type {
+    // the `{ ... }` of an inline object, properties are parsed the same way as in a def
+    pub fn parse(src: &str, node: &Node, aliases: &Aliases) -> Type {
+        let mut props = IndexMap::new();
+        let mut spreads = Vec::new();
+        let mut diagnostics = Vec::new();
+        let mut cursor = node.walk();
+        for child in node.named_children(&mut cursor) {
+            if child.kind() == "comment" || child.is_error() || child.is_missing() {
+                continue;
+            }
+            if child.kind() == "spread" {
+                match child.named_child(0).map(|x| reference::Type::from(src, &x)) {
+                    Some(Ok(x)) => spreads.push((x.reference, props.len(), child.range())),
+                    _ => diagnostics.push(Diagnostic::error(
+                        "only refs can be spread, e.g. `...#common`",
+                        child.range(),
+                    )),
+                }
+                continue;
+            }
+            match Prop::parse_with(src, &child, aliases) {
+                Ok(prop) if props.contains_key(&prop.name) => diagnostics.push(Diagnostic::error(
+                    format!("property `{}` is already defined", prop.name),
+                    prop.loc,
+                )),
+                Ok(prop) => {
+                    props.insert(prop.name.clone(), prop);
+                }
+                // already reported as a syntax error
+                Err(()) if child.has_error() => {}
+                Err(()) => {
+                    let typen = child
+                        .child_by_field_name("name")
+                        .and(child.child_by_field_name("type"));
+                    let message = match (child.kind(), typen) {
+                        ("property" | "optional", Some(x)) => {
+                            format!("`{}` types are not supported yet", x.kind())
+                        }
+                        ("property" | "optional", None) => {
+                            "property needs a name and a type".to_string()
+                        }
+                        (kind, _) => format!("unexpected `{kind}` in object body"),
+                    };
+                    diagnostics.push(Diagnostic::error(message, child.range()));
+                }
+            }
+        }
+        Type {
+            props,
+            spreads,
+            description: None,
+            diagnostics,
+            loc: node.range(),
+        }
+    }
+
+    pub fn check(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.diagnostics.clone();
+        for prop in self.props.values() {
+            diagnostics.extend(prop.value.check());
+        }
+        diagnostics
+    }
+}
+
+impl Into<AtpObject> for Type {
+    fn into(self) -> AtpObject {
+        let required: Vec<String> = self
+            .props
+            .values()
+            .filter(|x| !x.optional)
+            .map(|x| x.name.clone())
+            .collect();
+        AtpObject {
+            description: self.description,
+            properties: self
+                .props
+                .into_iter()
+                .map(|(name, prop)| (name, prop.value.into()))
+                .collect(),
+            required: (!required.is_empty()).then_some(required),
+            nullable: None,
+        }
+    }
+}
+
+impl Into<AtpTypes> for Type {
+    fn into(self) -> AtpTypes {
+        AtpTypes::Object(self.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tree_sitter::{Node, Parser, Tree};
+
+    use super::*;
+
+    fn unwrap_harness(tree: &Tree) -> Node {
+        tree.root_node().child(1).unwrap()
+    }
+
+    fn parse(src: &str) -> Tree {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_ana::LANGUAGE.into())
+            .expect("error loading ana grammar");
+        parser.parse(src, None).unwrap()
+    }
+
+    #[test]
+    fn base() {
+        let src = "@@[ Object ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let generic_type = GenericProp::from(src, &node).unwrap();
+        let object_type = Type::from(generic_type);
+        assert!(object_type.props.is_empty());
+        let AtpTypes::Object(object) = object_type.into() else {
+            panic!("Expected object type");
+        };
+        assert!(object.required == None);
+    }
+
+    #[test]
+    fn inline() {
+        let src = "@@[ { blob: Blob; alt?: String(len=..100); alt: String } ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let object_type = Type::parse(src, &node, &Aliases::default());
+        assert!(object_type.props.keys().collect::<Vec<_>>() == vec!["blob", "alt"]);
+        assert!(object_type.check().len() == 1);
+        let AtpTypes::Object(object) = object_type.into() else {
+            panic!("Expected object type");
+        };
+        assert!(object.required == Some(vec!["blob".to_string()]));
+    }
+}
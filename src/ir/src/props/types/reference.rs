@@ -0,0 +1,78 @@
+use lexicon::{AtpRef, AtpTypes};
+use tree_sitter::{Node, Range};
+
+use crate::NodeHelpers;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Type {
+    // e.g. #image or com.atproto.repo.strongRef
+    pub reference: String,
+    pub loc: Range,
+}
+
+impl Type {
+    pub fn from(src: &str, node: &Node) -> Result<Type, ()> {
+        match node.kind() {
+            "ref" => {
+                let id = node.child_by_field_name("id").unwrap_or(*node);
+                Ok(Type {
+                    reference: id.str(src),
+                    loc: node.range(),
+                })
+            }
+            _ => Err(()),
+        }
+    }
+}
+
+impl Into<AtpRef> for Type {
+    fn into(self) -> AtpRef {
+        AtpRef {
+            description: None,
+            reference: self.reference,
+        }
+    }
+}
+
+impl Into<AtpTypes> for Type {
+    fn into(self) -> AtpTypes {
+        AtpTypes::Ref(self.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tree_sitter::{Parser, Tree};
+
+    use super::*;
+
+    fn unwrap_harness(tree: &Tree) -> Node {
+        tree.root_node().child(1).unwrap()
+    }
+
+    fn parse(src: &str) -> Tree {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_ana::LANGUAGE.into())
+            .expect("error loading ana grammar");
+        parser.parse(src, None).unwrap()
+    }
+
+    #[test]
+    fn local() {
+        let src = "@@[ #image ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let ref_type = Type::from(src, &node).unwrap();
+        assert!(ref_type.reference.ends_with("image"));
+    }
+
+    #[test]
+    fn external() {
+        let src = "@@[ com.atproto.repo.strongRef ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let ref_type = Type::from(src, &node).unwrap();
+        assert!(ref_type.reference == "com.atproto.repo.strongRef");
+    }
+}
@@ -1,72 +1,159 @@
 use lexicon::{AtpString, AtpTypes, StringFormats};
 use tree_sitter::Range;
+use unicode_segmentation::UnicodeSegmentation;
 
-use crate::{ParamKind, Slice, props::GenericProp};
+use crate::{
+    ParamKind, Slice,
+    diagnostic::Diagnostic,
+    props::{GenericProp, ParamType},
+};
 
 #[derive(Debug, PartialEq)]
 pub struct Type {
     pub format: Option<StringFormats>,
     pub length: Slice,
     pub graphemes: Slice,
-    //known_values: todo!(),
-    //enumeration: todo!(),
+    pub known_values: Option<Vec<KnownValue>>,
+    pub enumeration: Option<Vec<String>>,
     pub default: Option<String>,
     pub constant: Option<String>,
+    pub description: Option<String>,
+    // unknown and mistyped params
+    pub diagnostics: Vec<Diagnostic>,
     pub loc: Range,
 }
 
+#[derive(Debug, PartialEq, Clone)]
+pub enum KnownValue {
+    // a ref to a token def, expanded to `nsid#name` once the lexicon is known
+    Token(String),
+    Literal(String),
+}
+
+pub const PARAMS: &[(&str, ParamType)] = &[
+    ("format", ParamType::Format),
+    ("len", ParamType::Slice),
+    ("graphemes", ParamType::Slice),
+    (
+        "known",
+        ParamType::List(&ParamType::Either(&ParamType::String, &ParamType::Ref)),
+    ),
+    ("enum", ParamType::List(&ParamType::String)),
+    ("default", ParamType::String),
+    ("const", ParamType::String),
+];
+
 impl From<GenericProp> for Type {
-    fn from(t: GenericProp) -> Self {
-        //let format = t.params.get("format");
-        let length = t
-            .params
-            .get("len")
-            .map_or(Slice::empty(), |x| match x.value {
-                ParamKind::Slice(s) => s,
-                _ => Slice::empty(),
-            });
-        let graphemes = t
-            .params
-            .get("graphemes")
-            .map_or(Slice::empty(), |x| match x.value {
-                ParamKind::Slice(s) => s,
-                _ => Slice::empty(),
-            });
-        let format = t.params.get("format").map_or(None, |x| match &x.value {
-            ParamKind::String(s) => Some(s),
+    fn from(mut t: GenericProp) -> Self {
+        t.expect(PARAMS);
+        let length = t.slice("len").unwrap_or(Slice::empty());
+        let graphemes = t.slice("graphemes").unwrap_or(Slice::empty());
+        // `format="did"` and `format=did` both work
+        let format = t.take("format", |x| match x {
+            ParamKind::String(s) | ParamKind::Ident(s) => Some(s.clone()),
             _ => None,
         });
-        let default = t.params.get("default").map_or(None, |x| match &x.value {
+        let default = t.string("default");
+        let constant = t.string("const");
+        let enumeration = t.list("enum", |x| match x {
             ParamKind::String(s) => Some(s.clone()),
             _ => None,
         });
-        let constant = t.params.get("constant").map_or(None, |x| match &x.value {
-            ParamKind::String(s) => Some(s.clone()),
+        let known_values = t.list("known", |x| match x {
+            ParamKind::Ref(r) => Some(KnownValue::Token(r.clone())),
+            ParamKind::String(s) => Some(KnownValue::Literal(s.clone())),
             _ => None,
         });
 
         Type {
-            format: format.and_then(|x| StringFormats::from_str(x)),
+            format: format.and_then(|x| StringFormats::from_str(&x)),
             length,
             graphemes,
+            known_values,
+            enumeration,
             default,
             constant,
+            description: t.string("description"),
+            diagnostics: t.finish(),
             loc: t.loc,
         }
     }
 }
 
+impl Type {
+    // default and const have to satisfy every other constraint on the string
+    pub fn check(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.diagnostics.clone();
+        for (param, slice) in [("len", &self.length), ("graphemes", &self.graphemes)] {
+            if !slice.fits_u32() {
+                diagnostics.push(Diagnostic::error(
+                    format!("{param}={slice} has to be between 0 and {}", u32::MAX),
+                    self.loc,
+                ));
+            }
+        }
+        for (param, value) in [("default", &self.default), ("const", &self.constant)] {
+            let Some(value) = value else {
+                continue;
+            };
+            if let Some(enumeration) = &self.enumeration
+                && !enumeration.contains(value)
+            {
+                diagnostics.push(Diagnostic::error(
+                    format!("{param} {value:?} isn't one of the enum values"),
+                    self.loc,
+                ));
+            }
+            let len = value.len();
+            if !self.length.contains(len as i64) {
+                diagnostics.push(Diagnostic::error(
+                    format!(
+                        "{param} {value:?} is {len} bytes long, outside of len={}",
+                        self.length
+                    ),
+                    self.loc,
+                ));
+            }
+            let graphemes = value.graphemes(true).count();
+            if !self.graphemes.contains(graphemes as i64) {
+                diagnostics.push(Diagnostic::error(
+                    format!(
+                        "{param} {value:?} is {graphemes} graphemes long, outside of graphemes={}",
+                        self.graphemes
+                    ),
+                    self.loc,
+                ));
+            }
+        }
+        if let (Some(default), Some(constant)) = (&self.default, &self.constant)
+            && default != constant
+        {
+            diagnostics.push(Diagnostic::error(
+                format!("default {default:?} can't be used, the value is always {constant:?}"),
+                self.loc,
+            ));
+        }
+        diagnostics
+    }
+}
+
 impl Into<AtpString> for Type {
     fn into(self) -> AtpString {
         AtpString {
-            description: None,
+            description: self.description,
             format: self.format,
             min_length: self.length.start.and_then(|x| x.try_into().ok()),
             max_length: self.length.end.and_then(|x| x.try_into().ok()),
             min_graphemes: self.graphemes.start.and_then(|x| x.try_into().ok()),
             max_graphemes: self.graphemes.end.and_then(|x| x.try_into().ok()),
-            known_values: None,
-            enumeration: None,
+            known_values: self.known_values.map(|x| {
+                x.into_iter()
+                    .map(|x| match x {
+                        KnownValue::Token(s) | KnownValue::Literal(s) => s,
+                    })
+                    .collect()
+            }),
+            enumeration: self.enumeration,
             default: self.default,
             constant: self.constant,
         }
@@ -147,6 +234,75 @@ mod tests {
         assert!(string_type.format == Some(StringFormats::Did));
     }
 
+    #[test]
+    fn known() {
+        let src = "@@[ String(known=[#foo, \"bar\"]) ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let generic_type = GenericProp::from(src, &node).unwrap();
+        let string_type = Type::from(generic_type);
+        assert!(
+            string_type.known_values
+                == Some(vec![
+                    KnownValue::Token("#foo".to_string()),
+                    KnownValue::Literal("bar".to_string()),
+                ])
+        );
+    }
+
+    #[test]
+    fn enum_and_const() {
+        let src = "@@[ String(enum=[\"a\", \"b\"], default=\"a\", const=\"b\") ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let generic_type = GenericProp::from(src, &node).unwrap();
+        let string_type = Type::from(generic_type);
+        assert!(string_type.enumeration == Some(vec!["a".to_string(), "b".to_string()]));
+        assert!(string_type.default == Some("a".to_string()));
+        assert!(string_type.constant == Some("b".to_string()));
+        // default and const disagree
+        assert!(string_type.check().len() == 1);
+    }
+
+    #[test]
+    fn escapes_and_description() {
+        let src = "@@[ String(default=\"say \\\"hi\\\"\\n\", description=\"\"\"\n    a greeting,\n      indented\n    \"\"\") ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let generic_type = GenericProp::from(src, &node).unwrap();
+        let string_type = Type::from(generic_type);
+        assert!(string_type.default == Some("say \"hi\"\n".to_string()));
+        let lowered: AtpString = string_type.into();
+        assert!(lowered.description == Some("a greeting,\n  indented".to_string()));
+
+        let src = r###"@@[ String(default=r#"say "hi""#, const=r"C:\raw") ]@@"###;
+        let tree = parse(src);
+        let node = unwrap_harness(&tree);
+        let string_type = Type::from(GenericProp::from(src, &node).unwrap());
+        assert!(string_type.default == Some("say \"hi\"".to_string()));
+        assert!(string_type.constant == Some("C:\\raw".to_string()));
+    }
+
+    #[test]
+    fn check() {
+        let src = "@@[ String(len=..3, graphemes=..1, enum=[\"a\"], default=\"👍🏽\") ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let generic_type = GenericProp::from(src, &node).unwrap();
+        let messages: Vec<String> = Type::from(generic_type)
+            .check()
+            .into_iter()
+            .map(|x| x.message)
+            .collect();
+        assert!(
+            messages
+                == vec![
+                    "default \"👍🏽\" isn't one of the enum values",
+                    "default \"👍🏽\" is 8 bytes long, outside of len=..3",
+                ]
+        );
+    }
+
     #[test]
     fn complex() {
         let src = "@@[ String(len=42..69, format=\"did\") ]@@";
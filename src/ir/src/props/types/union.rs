@@ -0,0 +1,111 @@
+use lexicon::{AtpTypes, AtpUnion};
+use tree_sitter::Range;
+
+use crate::{
+    ParamKind,
+    diagnostic::Diagnostic,
+    props::{GenericProp, ParamType},
+};
+
+#[derive(Debug, PartialEq)]
+pub struct Type {
+    pub refs: Vec<String>,
+    pub closed: Option<bool>,
+    pub description: Option<String>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub loc: Range,
+}
+
+pub const PARAMS: &[(&str, ParamType)] = &[
+    ("refs", ParamType::List(&ParamType::Ref)),
+    ("closed", ParamType::Boolean),
+];
+
+impl From<GenericProp> for Type {
+    fn from(mut t: GenericProp) -> Self {
+        t.expect(PARAMS);
+        let refs = t.list("refs", |x| match x {
+            ParamKind::Ref(r) => Some(r.clone()),
+            _ => None,
+        });
+        let closed = t.boolean("closed");
+
+        Type {
+            refs: refs.unwrap_or_default(),
+            closed,
+            description: t.string("description"),
+            diagnostics: t.finish(),
+            loc: t.loc,
+        }
+    }
+}
+
+impl Type {
+    pub fn check(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.diagnostics.clone();
+        if self.closed == Some(true) && self.refs.is_empty() {
+            diagnostics.push(Diagnostic::error(
+                "a closed union needs at least one ref in `refs`",
+                self.loc,
+            ));
+        }
+        diagnostics
+    }
+}
+
+impl Into<AtpUnion> for Type {
+    fn into(self) -> AtpUnion {
+        AtpUnion {
+            description: self.description,
+            refs: self.refs,
+            closed: self.closed,
+        }
+    }
+}
+
+impl Into<AtpTypes> for Type {
+    fn into(self) -> AtpTypes {
+        AtpTypes::Union(self.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tree_sitter::{Node, Parser, Tree};
+
+    use super::*;
+
+    fn unwrap_harness(tree: &Tree) -> Node {
+        tree.root_node().child(1).unwrap()
+    }
+
+    fn parse(src: &str) -> Tree {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_ana::LANGUAGE.into())
+            .expect("error loading ana grammar");
+        parser.parse(src, None).unwrap()
+    }
+
+    #[test]
+    fn refs() {
+        let src = "@@[ Union(refs=[#a, com.example.b], closed=true) ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let generic_type = GenericProp::from(src, &node).unwrap();
+        let union_type = Type::from(generic_type);
+        assert!(union_type.refs == vec!["#a".to_string(), "com.example.b".to_string()]);
+        assert!(union_type.closed == Some(true));
+        assert!(union_type.check().is_empty());
+    }
+
+    #[test]
+    fn closed_without_refs() {
+        let src = "@@[ Union(closed=true) ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let generic_type = GenericProp::from(src, &node).unwrap();
+        let union_type = Type::from(generic_type);
+        assert!(union_type.check().len() == 1);
+    }
+}
@@ -0,0 +1,77 @@
+use lexicon::{AtpTypes, AtpUnknown};
+use tree_sitter::Range;
+
+use crate::{
+    diagnostic::Diagnostic,
+    props::{GenericProp, ParamType},
+};
+
+#[derive(Debug, PartialEq)]
+pub struct Type {
+    pub description: Option<String>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub loc: Range,
+}
+
+pub const PARAMS: &[(&str, ParamType)] = &[];
+
+impl From<GenericProp> for Type {
+    fn from(mut t: GenericProp) -> Self {
+        t.expect(PARAMS);
+        Type {
+            description: t.string("description"),
+            diagnostics: t.finish(),
+            loc: t.loc,
+        }
+    }
+}
+
+impl Type {
+    pub fn check(&self) -> Vec<Diagnostic> {
+        self.diagnostics.clone()
+    }
+}
+
+impl Into<AtpUnknown> for Type {
+    fn into(self) -> AtpUnknown {
+        AtpUnknown {
+            description: self.description,
+        }
+    }
+}
+
+impl Into<AtpTypes> for Type {
+    fn into(self) -> AtpTypes {
+        AtpTypes::Unknown(self.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tree_sitter::{Node, Parser, Tree};
+
+    use super::*;
+
+    fn unwrap_harness(tree: &Tree) -> Node {
+        tree.root_node().child(1).unwrap()
+    }
+
+    fn parse(src: &str) -> Tree {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_ana::LANGUAGE.into())
+            .expect("error loading ana grammar");
+        parser.parse(src, None).unwrap()
+    }
+
+    #[test]
+    fn base() {
+        let src = "@@[ Unknown ]@@";
+        let tree = parse(&src);
+        let node = unwrap_harness(&tree);
+        let generic_type = GenericProp::from(src, &node).unwrap();
+        let unknown_type = Type::from(generic_type);
+        assert!(unknown_type.loc.start_byte == 4);
+        assert!(unknown_type.check().is_empty());
+    }
+}
@@ -0,0 +1,61 @@
+/// version of the bundled lexicon set, bumped whenever a file in `std/` changes
+pub const STD_VERSION: u32 = 1;
+
+/// core `com.atproto.*` lexicons shipped with the crate, as (path below `std/`, json)
+pub const STD: &[(&str, &str)] = &[
+    std_lexicon!("com/atproto/label/defs.json"),
+    std_lexicon!("com/atproto/moderation/defs.json"),
+    std_lexicon!("com/atproto/repo/defs.json"),
+    std_lexicon!("com/atproto/repo/strongRef.json"),
+];
+
+macro_rules! std_lexicon {
+    ($path:literal) => {
+        ($path, include_str!(concat!("../std/", $path)))
+    };
+}
+use std_lexicon;
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::Catalog;
+
+    use super::*;
+
+    #[test]
+    fn parses() {
+        let catalog = Catalog::with_std();
+        for (path, _) in STD {
+            let nsid = path.trim_end_matches(".json").replace('/', ".");
+            assert!(catalog.contains(&nsid), "{path} isn't {nsid}");
+            assert_eq!(
+                catalog.path(&nsid),
+                Some(Path::new("std").join(path).as_path())
+            );
+        }
+        assert!(catalog.resolve("com.atproto.repo.strongRef").is_some());
+        assert!(
+            catalog
+                .resolve("com.atproto.label.defs#selfLabels")
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn overridden() {
+        let mut catalog = Catalog::with_std();
+        catalog
+            .load_str(
+                Path::new("strongRef.json"),
+                r#"{ "lexicon": 1, "id": "com.atproto.repo.strongRef", "defs": {} }"#,
+            )
+            .unwrap();
+        assert_eq!(
+            catalog.path("com.atproto.repo.strongRef"),
+            Some(Path::new("strongRef.json"))
+        );
+        assert!(catalog.resolve("com.atproto.repo.strongRef").is_none());
+    }
+}
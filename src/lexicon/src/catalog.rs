@@ -0,0 +1,375 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use format_serde_error::SerdeError;
+
+use crate::{AtpTypes, Lexicon, bundled};
+
+/// a set of lexicons indexed by NSID, used to resolve refs that point outside of the lexicon being worked on
+#[derive(Debug, Default)]
+pub struct Catalog {
+    lexicons: HashMap<String, Entry>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    path: PathBuf,
+    lexicon: Lexicon,
+    // shipped with the crate, anything loaded from disk replaces it
+    bundled: bool,
+}
+
+#[derive(Debug)]
+pub enum CatalogError {
+    /// a file or directory couldn't be read
+    Io { path: PathBuf, message: String },
+    /// a file isn't a valid lexicon, `message` includes the offending lines
+    Parse {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// two files declare the same NSID, the first one is kept
+    Duplicate {
+        nsid: String,
+        first: PathBuf,
+        second: PathBuf,
+    },
+}
+
+impl Display for CatalogError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CatalogError::Io { path, message } => write!(f, "{}: {message}", path.display()),
+            CatalogError::Parse {
+                path,
+                line,
+                column,
+                message,
+            } => write!(f, "{}:{line}:{column}: {message}", path.display()),
+            CatalogError::Duplicate {
+                nsid,
+                first,
+                second,
+            } => write!(
+                f,
+                "{}: {nsid} is already defined in {}",
+                second.display(),
+                first.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CatalogError {}
+
+impl Catalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// a catalog with the bundled `com.atproto.*` lexicons already loaded
+    pub fn with_std() -> Self {
+        let mut catalog = Self::new();
+        for (path, src) in bundled::STD {
+            let lexicon: Lexicon = serde_json::from_str(src).expect("bundled lexicons are valid");
+            catalog.lexicons.insert(
+                lexicon.id.clone(),
+                Entry {
+                    path: Path::new("std").join(path),
+                    lexicon,
+                    bundled: true,
+                },
+            );
+        }
+        catalog
+    }
+
+    /// loads every `*.json` file below `dir`, errors don't stop the rest of the tree from loading
+    pub fn load_dir(&mut self, dir: &Path) -> Vec<CatalogError> {
+        let mut errors = Vec::new();
+        let mut stack = vec![dir.to_path_buf()];
+        while let Some(dir) = stack.pop() {
+            let entries = match fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    errors.push(CatalogError::Io {
+                        path: dir,
+                        message: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+            let mut entries: Vec<PathBuf> =
+                entries.filter_map(|x| x.ok()).map(|x| x.path()).collect();
+            // sorted so duplicates are reported the same way every time
+            entries.sort();
+            for path in entries.into_iter().rev() {
+                if path.is_dir() {
+                    stack.push(path);
+                } else if path.extension().is_some_and(|x| x == "json")
+                    && let Err(e) = self.load_file(&path)
+                {
+                    errors.push(e);
+                }
+            }
+        }
+        errors
+    }
+
+    pub fn load_file(&mut self, path: &Path) -> Result<&Lexicon, CatalogError> {
+        let src = fs::read_to_string(path).map_err(|e| CatalogError::Io {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })?;
+        self.load_str(path, &src)
+    }
+
+    /// parses a lexicon, `path` is only used for error messages
+    pub fn load_str(&mut self, path: &Path, src: &str) -> Result<&Lexicon, CatalogError> {
+        let lexicon: Lexicon = serde_json::from_str(src).map_err(|e| CatalogError::Parse {
+            path: path.to_path_buf(),
+            line: e.line(),
+            column: e.column(),
+            message: SerdeError::new(src.to_string(), e).to_string(),
+        })?;
+        self.insert(path, lexicon)
+    }
+
+    pub fn insert(&mut self, path: &Path, lexicon: Lexicon) -> Result<&Lexicon, CatalogError> {
+        if let Some(existing) = self.lexicons.get(&lexicon.id).filter(|x| !x.bundled) {
+            return Err(CatalogError::Duplicate {
+                nsid: lexicon.id,
+                first: existing.path.clone(),
+                second: path.to_path_buf(),
+            });
+        }
+        let nsid = lexicon.id.clone();
+        self.lexicons.insert(
+            nsid.clone(),
+            Entry {
+                path: path.to_path_buf(),
+                lexicon,
+                bundled: false,
+            },
+        );
+        Ok(&self.lexicons[&nsid].lexicon)
+    }
+
+    /// puts a lexicon in place of any other with the same NSID, for sources that are still being edited
+    pub fn replace(&mut self, path: &Path, lexicon: Lexicon) {
+        self.lexicons.insert(
+            lexicon.id.clone(),
+            Entry {
+                path: path.to_path_buf(),
+                lexicon,
+                bundled: false,
+            },
+        );
+    }
+
+    pub fn remove(&mut self, nsid: &str) -> Option<Lexicon> {
+        self.lexicons.remove(nsid).map(|x| x.lexicon)
+    }
+
+    pub fn get(&self, nsid: &str) -> Option<&Lexicon> {
+        self.lexicons.get(nsid).map(|x| &x.lexicon)
+    }
+
+    /// the file a lexicon was loaded from
+    pub fn path(&self, nsid: &str) -> Option<&Path> {
+        self.lexicons.get(nsid).map(|x| x.path.as_path())
+    }
+
+    pub fn contains(&self, nsid: &str) -> bool {
+        self.lexicons.contains_key(nsid)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lexicons.is_empty()
+    }
+
+    /// every lexicon, sorted by NSID
+    pub fn lexicons(&self) -> Vec<&Lexicon> {
+        let mut lexicons: Vec<&Lexicon> = self.lexicons.values().map(|x| &x.lexicon).collect();
+        lexicons.sort_by(|a, b| a.id.cmp(&b.id));
+        lexicons
+    }
+
+    /// every definition as `nsid#def`, sorted
+    pub fn ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .lexicons
+            .values()
+            .flat_map(|x| {
+                x.lexicon
+                    .defs
+                    .keys()
+                    .map(|d| format!("{}#{d}", x.lexicon.id))
+            })
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// resolves a fully qualified ref, `nsid` alone means `nsid#main`
+    pub fn resolve(&self, reference: &str) -> Option<&AtpTypes> {
+        let (nsid, def) = reference.split_once('#').unwrap_or((reference, "main"));
+        self.get(nsid)?.defs.get(def)
+    }
+
+    /// checks the key of a record against the record definition of its collection
+    pub fn check_record_key(&self, collection: &str, rkey: &str) -> Result<(), String> {
+        match self.resolve(collection) {
+            Some(AtpTypes::Record(record)) => record.key.check(rkey),
+            Some(other) => Err(format!("{collection} is a {}, not a record", other.kind())),
+            None => Err(format!("unknown collection {collection}")),
+        }
+    }
+
+    /// resolves a ref as written inside the lexicon `base`, local `#def` refs point into `base`
+    pub fn resolve_from(&self, base: &str, reference: &str) -> Option<&AtpTypes> {
+        match reference.strip_prefix('#') {
+            Some(def) => self.get(base)?.defs.get(def),
+            None => self.resolve(reference),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lexicon(nsid: &str) -> String {
+        format!(
+            r#"{{ "lexicon": 1, "id": "{nsid}", "defs": {{
+                "main": {{ "type": "object", "properties": {{}} }},
+                "other": {{ "type": "string" }}
+            }} }}"#
+        )
+    }
+
+    #[test]
+    fn resolve() {
+        let mut catalog = Catalog::new();
+        catalog
+            .load_str(Path::new("a.json"), &lexicon("com.example.a"))
+            .unwrap();
+
+        assert!(matches!(
+            catalog.resolve("com.example.a"),
+            Some(AtpTypes::Object(_))
+        ));
+        assert!(matches!(
+            catalog.resolve("com.example.a#other"),
+            Some(AtpTypes::String(_))
+        ));
+        assert!(matches!(
+            catalog.resolve_from("com.example.a", "#other"),
+            Some(AtpTypes::String(_))
+        ));
+        assert!(catalog.resolve("com.example.a#missing").is_none());
+        assert!(catalog.resolve("com.example.b").is_none());
+        assert_eq!(
+            catalog.ids(),
+            vec!["com.example.a#main", "com.example.a#other"]
+        );
+    }
+
+    #[test]
+    fn record_key() {
+        let mut catalog = Catalog::new();
+        catalog
+            .load_str(
+                Path::new("profile.json"),
+                r#"{ "lexicon": 1, "id": "com.example.profile", "defs": { "main": {
+                    "type": "record", "key": "literal:self", "record": { "type": "object", "properties": {} }
+                } } }"#,
+            )
+            .unwrap();
+        assert!(
+            catalog
+                .check_record_key("com.example.profile", "self")
+                .is_ok()
+        );
+        assert!(
+            catalog
+                .check_record_key("com.example.profile", "3jzfcijpj2z2a")
+                .is_err()
+        );
+        assert!(
+            catalog
+                .check_record_key("com.example.other", "self")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn duplicate() {
+        let mut catalog = Catalog::new();
+        catalog
+            .load_str(Path::new("a.json"), &lexicon("com.example.a"))
+            .unwrap();
+        let err = catalog
+            .load_str(Path::new("b.json"), &lexicon("com.example.a"))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "b.json: com.example.a is already defined in a.json"
+        );
+        assert_eq!(catalog.path("com.example.a"), Some(Path::new("a.json")));
+    }
+
+    #[test]
+    fn replace() {
+        let mut catalog = Catalog::new();
+        catalog
+            .load_str(Path::new("a.json"), &lexicon("com.example.a"))
+            .unwrap();
+        let mut edited: Lexicon = serde_json::from_str(&lexicon("com.example.a")).unwrap();
+        edited.defs.shift_remove("other");
+        catalog.replace(Path::new("a.ana"), edited);
+        assert_eq!(catalog.path("com.example.a"), Some(Path::new("a.ana")));
+        assert!(catalog.resolve("com.example.a#other").is_none());
+        assert!(catalog.remove("com.example.a").is_some());
+        assert!(!catalog.contains("com.example.a"));
+    }
+
+    #[test]
+    fn parse_error() {
+        let mut catalog = Catalog::new();
+        let err = catalog
+            .load_str(
+                Path::new("a.json"),
+                "{\n  \"lexicon\": 1,\n  \"id\": 5,\n  \"defs\": {}\n}",
+            )
+            .unwrap_err();
+        let CatalogError::Parse { path, line, .. } = err else {
+            panic!("expected a parse error, got {err:?}");
+        };
+        assert_eq!(path, Path::new("a.json"));
+        assert_eq!(line, 3);
+    }
+
+    #[test]
+    fn load_dir() {
+        let dir = std::env::temp_dir().join(format!("ana-catalog-{}", std::process::id()));
+        fs::create_dir_all(dir.join("com/example")).unwrap();
+        fs::write(dir.join("com/example/a.json"), lexicon("com.example.a")).unwrap();
+        fs::write(dir.join("com/example/b.json"), lexicon("com.example.b")).unwrap();
+        fs::write(dir.join("com/example/notes.txt"), "not a lexicon").unwrap();
+
+        let mut catalog = Catalog::new();
+        let errors = catalog.load_dir(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(errors.is_empty(), "{errors:?}");
+        let ids: Vec<&str> = catalog.lexicons().iter().map(|x| x.id.as_str()).collect();
+        assert_eq!(ids, vec!["com.example.a", "com.example.b"]);
+    }
+}
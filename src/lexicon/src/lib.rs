@@ -1,14 +1,24 @@
 #[macro_use]
 mod macros;
+mod bundled;
+mod catalog;
+mod record_key;
+mod strict;
 
-use std::{collections::HashMap, fmt::Display};
+pub use bundled::{STD, STD_VERSION};
+pub use catalog::{Catalog, CatalogError};
+pub use record_key::RecordKey;
+pub use strict::StrictError;
 
+use std::fmt::Display;
+
+pub use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use subenum::subenum;
 
 #[skip_serializing_none]
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 /// Lexicons are JSON files associated with a single NSID. A file contains one or more definitions, each with a distinct short name. A definition with the name `main` optionally describes the "primary" definition for the entire file. A Lexicon with zero definitions is invalid.
 pub struct Lexicon {
@@ -20,8 +30,8 @@ pub struct Lexicon {
     pub revision: Option<i32>,
     /// short overview of the Lexicon, usually one or two sentences
     pub description: Option<String>,
-    /// set of definitions, each with a distinct name (key)
-    pub defs: HashMap<String, AtpTypes>,
+    /// set of definitions, each with a distinct name (key), in declaration order
+    pub defs: IndexMap<String, AtpTypes>,
 }
 
 schema_type!(
@@ -54,17 +64,17 @@ r###"{
 
 schema_type!(AtpInteger, "integer", {
     /// minimum acceptable value
-    pub minimum: Option<i32>,
+    pub minimum: Option<i64>,
     /// maximum acceptable value
-    pub maximum: Option<i32>,
+    pub maximum: Option<i64>,
     /// a closed set of allowed values
     #[serde(rename="enum")]
-    pub enumeration: Option<Vec<i32>>,
+    pub enumeration: Option<Vec<i64>>,
     /// a default value for this field
-    pub default: Option<i32>,
+    pub default: Option<i64>,
     /// a fixed (constant) value for this field
     #[serde(rename="const")]
-    pub constant: Option<i32>
+    pub constant: Option<i64>
 }, {
   minimum: None,
   maximum: None,
@@ -80,7 +90,9 @@ schema_type!(AtpInteger, "integer", {
     "enum": [0, 1]
 }"###);
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, derive_display_from_debug::Display)]
+#[derive(
+    Serialize, Deserialize, Debug, Clone, PartialEq, Eq, derive_display_from_debug::Display,
+)]
 #[serde(rename_all = "kebab-case")]
 pub enum StringFormats {
     AtIdentifier,
@@ -96,7 +108,25 @@ pub enum StringFormats {
     Language,
 }
 
+/// the largest integer a javascript number holds exactly, atproto integers should stay within ±this
+pub const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
 impl StringFormats {
+    /// every format as it's written in lexicons
+    pub const NAMES: &'static [&'static str] = &[
+        "at-identifier",
+        "at-uri",
+        "cid",
+        "datetime",
+        "did",
+        "handle",
+        "nsid",
+        "tid",
+        "uri",
+        "record-key",
+        "language",
+    ];
+
     pub fn from_str(s: &str) -> Option<Self> {
         match s {
             "at-identifier" => Some(StringFormats::AtIdentifier),
@@ -239,13 +269,13 @@ schema_type!(AtpArray, "array", {
 
 schema_type!(AtpObject, "object", {
     /// defines the properties (fields) by name, each with their own schema
-    pub properties: HashMap<String, AtpTypes>,
+    pub properties: IndexMap<String, AtpTypes>,
     /// indicates which properties are required
     pub required: Option<Vec<String>>,
     /// indicates which properties can have null as a value
     pub nullable: Option<Vec<String>>
 }, {
-  properties: HashMap::new(),
+  properties: IndexMap::new(),
   required: None,
   nullable: None
 }, r###"{
@@ -281,7 +311,10 @@ schema_type!(AtpObject, "object", {
           }
 }}"###);
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, derive_display_from_debug::Display)]
+#[skip_serializing_none]
+#[derive(
+    Serialize, Deserialize, Debug, Clone, PartialEq, Eq, derive_display_from_debug::Display,
+)]
 #[serde(tag = "type")]
 #[serde(rename_all = "lowercase")]
 pub enum ParamProps {
@@ -289,7 +322,9 @@ pub enum ParamProps {
     Integer(AtpInteger),
     String(AtpString),
     Unknown(AtpUnknown),
+    #[serde(rename_all = "camelCase")]
     Array {
+        description: Option<String>,
         items: Box<ParamProps>,
         min_length: Option<u32>,
         max_length: Option<u32>,
@@ -300,10 +335,10 @@ schema_type!(AtpParams, "params", {
     /// same semantics as field on `object`
     pub required: Option<Vec<String>>,
     /// similar to properties under `object`, but can only include the types `boolean`, `integer`, `string`, and `unknown`; or an `array` of one of these types
-    pub properties: HashMap<String, ParamProps>
+    pub properties: IndexMap<String, ParamProps>
 }, {
   required: None,
-  properties: HashMap::new()
+  properties: IndexMap::new()
 }, r###"{
 				"type": "params",
 				"required": ["did", "rkey"],
@@ -370,11 +405,11 @@ schema_type!(
 
 schema_type!(AtpRecord, "record", {
     /// specifies the Record Key `type` (e.g. tid)
-    pub key: String, // def make an enum for this
+    pub key: RecordKey,
     /// a schema definition with type `object`, which specifies this type of record
     pub record: AtpObject
 }, {
-  key: "tid".to_string(),
+  key: RecordKey::Tid,
   record: AtpObject::new()
 }, r###"{
       "type": "record",
@@ -415,7 +450,9 @@ schema_type!(AtpRecord, "record", {
       }
     }"###);
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, derive_display_from_debug::Display)]
+#[derive(
+    Serialize, Deserialize, Debug, Clone, PartialEq, Eq, derive_display_from_debug::Display,
+)]
 #[serde(tag = "type")]
 #[serde(rename_all = "lowercase")]
 /// schema definition, either an object, a ref, or a union of refs. Used to describe JSON encoded responses, though schema is optional even for JSON responses.
@@ -426,7 +463,9 @@ pub enum RpcSchema {
 }
 
 #[skip_serializing_none]
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, derive_display_from_debug::Display)]
+#[derive(
+    Serialize, Deserialize, Debug, Clone, PartialEq, Eq, derive_display_from_debug::Display,
+)]
 pub struct RpcIO {
     pub description: Option<String>,
     pub encoding: String,
@@ -434,7 +473,9 @@ pub struct RpcIO {
 }
 
 #[skip_serializing_none]
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, derive_display_from_debug::Display)]
+#[derive(
+    Serialize, Deserialize, Debug, Clone, PartialEq, Eq, derive_display_from_debug::Display,
+)]
 pub struct RpcError {
     /// short name for the error type, with no whitespace
     pub name: String,
@@ -524,7 +565,9 @@ schema_type!(AtpProcedure, "procedure", {
     }"###);
 
 #[skip_serializing_none]
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, derive_display_from_debug::Display)]
+#[derive(
+    Serialize, Deserialize, Debug, Clone, PartialEq, Eq, derive_display_from_debug::Display,
+)]
 pub struct RpcMessage {
     pub description: Option<String>,
     pub schema: AtpUnion,
@@ -574,7 +617,9 @@ schema_type!(AtpSubscription, "subscription", {
     }"###);
 
 #[subenum(Field)]
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, derive_display_from_debug::Display)]
+#[derive(
+    Serialize, Deserialize, Debug, Clone, PartialEq, Eq, derive_display_from_debug::Display,
+)]
 #[serde(tag = "type")]
 #[serde(rename_all = "lowercase")]
 pub enum AtpTypes {
@@ -589,6 +634,7 @@ pub enum AtpTypes {
     #[subenum(Field)]
     Bytes(AtpBytes),
     #[subenum(Field)]
+    #[serde(rename = "cid-link")]
     CidLink(AtpCidLink),
     #[subenum(Field)]
     Blob(AtpBlob),
@@ -612,12 +658,117 @@ pub enum AtpTypes {
     Subscription(AtpSubscription),
 }
 
+impl Lexicon {
+    /// every ref and union member in this lexicon, as written (`#local`, `nsid` or `nsid#def`)
+    pub fn references(&self) -> Vec<&str> {
+        let mut refs = Vec::new();
+        for def in self.defs.values() {
+            def.collect_references(&mut refs);
+        }
+        refs
+    }
+}
+
+impl RpcIO {
+    fn collect_references<'a>(&'a self, out: &mut Vec<&'a str>) {
+        match &self.schema {
+            Some(RpcSchema::Object(x)) => x.collect_references(out),
+            Some(RpcSchema::Ref(x)) => out.push(&x.reference),
+            Some(RpcSchema::Union(x)) => out.extend(x.refs.iter().map(String::as_str)),
+            None => {}
+        }
+    }
+}
+
+impl AtpObject {
+    fn collect_references<'a>(&'a self, out: &mut Vec<&'a str>) {
+        for prop in self.properties.values() {
+            prop.collect_references(out);
+        }
+    }
+}
+
+impl AtpTypes {
+    fn collect_references<'a>(&'a self, out: &mut Vec<&'a str>) {
+        match self {
+            AtpTypes::Ref(x) => out.push(&x.reference),
+            AtpTypes::Union(x) => out.extend(x.refs.iter().map(String::as_str)),
+            AtpTypes::Array(x) => x.items.collect_references(out),
+            AtpTypes::Object(x) => x.collect_references(out),
+            AtpTypes::Record(x) => x.record.collect_references(out),
+            AtpTypes::Query(x) => {
+                if let Some(output) = &x.output {
+                    output.collect_references(out);
+                }
+            }
+            AtpTypes::Procedure(x) => {
+                for io in [&x.input, &x.output].into_iter().flatten() {
+                    io.collect_references(out);
+                }
+            }
+            AtpTypes::Subscription(x) => {
+                out.extend(x.message.schema.refs.iter().map(String::as_str))
+            }
+            _ => {}
+        }
+    }
+
+    /// the `type` tag this definition serializes with
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AtpTypes::Null(_) => "null",
+            AtpTypes::Boolean(_) => "boolean",
+            AtpTypes::Integer(_) => "integer",
+            AtpTypes::String(_) => "string",
+            AtpTypes::Bytes(_) => "bytes",
+            AtpTypes::CidLink(_) => "cid-link",
+            AtpTypes::Blob(_) => "blob",
+            AtpTypes::Array(_) => "array",
+            AtpTypes::Object(_) => "object",
+            AtpTypes::Params(_) => "params",
+            AtpTypes::Token(_) => "token",
+            AtpTypes::Ref(_) => "ref",
+            AtpTypes::Union(_) => "union",
+            AtpTypes::Unknown(_) => "unknown",
+            AtpTypes::Record(_) => "record",
+            AtpTypes::Query(_) => "query",
+            AtpTypes::Procedure(_) => "procedure",
+            AtpTypes::Subscription(_) => "subscription",
+        }
+    }
+
+    /// the description of whichever type this is
+    pub fn description(&self) -> Option<&String> {
+        match self {
+            AtpTypes::Null(x) => x.description.as_ref(),
+            AtpTypes::Boolean(x) => x.description.as_ref(),
+            AtpTypes::Integer(x) => x.description.as_ref(),
+            AtpTypes::String(x) => x.description.as_ref(),
+            AtpTypes::Bytes(x) => x.description.as_ref(),
+            AtpTypes::CidLink(x) => x.description.as_ref(),
+            AtpTypes::Blob(x) => x.description.as_ref(),
+            AtpTypes::Array(x) => x.description.as_ref(),
+            AtpTypes::Object(x) => x.description.as_ref(),
+            AtpTypes::Params(x) => x.description.as_ref(),
+            AtpTypes::Token(x) => x.description.as_ref(),
+            AtpTypes::Ref(x) => x.description.as_ref(),
+            AtpTypes::Union(x) => x.description.as_ref(),
+            AtpTypes::Unknown(x) => x.description.as_ref(),
+            AtpTypes::Record(x) => x.description.as_ref(),
+            AtpTypes::Query(x) => x.description.as_ref(),
+            AtpTypes::Procedure(x) => x.description.as_ref(),
+            AtpTypes::Subscription(x) => x.description.as_ref(),
+        }
+    }
+}
+
+#[cfg(test)]
 mod test {
     use serde::{Deserialize, Serialize};
 
     #[test]
     fn integ() {
-        use crate::lexicon::*;
+        use crate::*;
         let a: Field = serde_json::from_str(
             r###"{
               "type": "boolean",
@@ -638,13 +789,20 @@ mod test {
         );
     }
 
-    #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
     #[serde(tag = "type")]
     #[serde(deny_unknown_fields)]
     struct Test {
         description: Option<String>,
     }
 
+    #[test]
+    fn order() {
+        let src = r#"{"lexicon":1,"id":"com.example.order","defs":{"main":{"type":"object","properties":{"z":{"type":"string"},"a":{"type":"integer"},"m":{"type":"boolean"}}},"b":{"type":"token"},"a":{"type":"token"}}}"#;
+        let lexicon: crate::Lexicon = serde_json::from_str(src).unwrap();
+        assert_eq!(serde_json::to_string(&lexicon).unwrap(), src);
+    }
+
     #[test]
     fn test() {
         let test = Test { description: None };
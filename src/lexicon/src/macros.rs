@@ -33,7 +33,7 @@ macro_rules! create_type_test {
 macro_rules! schema_type {
     ($name:ident, $type:literal, { $($body:tt)* }, { $($default:tt)* }, $example:literal) => {
         #[skip_serializing_none]
-        #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+        #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
         // tagging structs just does not work, you'll have to go through the AtpTypes enum to generate a valid instance
         //#[serde(tag = "type")]
         //#[serde(rename = $type)]
@@ -0,0 +1,168 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// how the keys of a record collection are formed
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum RecordKey {
+    /// a timestamp identifier, the usual choice for collections that grow over time
+    #[default]
+    Tid,
+    /// an NSID
+    Nsid,
+    /// any string that is valid record key syntax
+    Any,
+    /// exactly this value, for collections with a single record such as profiles
+    Literal(String),
+}
+
+impl RecordKey {
+    /// whether `rkey` is a valid key for a record of this type
+    pub fn check(&self, rkey: &str) -> Result<(), String> {
+        is_record_key(rkey)?;
+        match self {
+            RecordKey::Tid => is_tid(rkey),
+            RecordKey::Nsid => is_nsid(rkey),
+            RecordKey::Any => Ok(()),
+            RecordKey::Literal(value) if value == rkey => Ok(()),
+            RecordKey::Literal(value) => Err(format!("record key must be `{value}`")),
+        }
+    }
+}
+
+impl FromStr for RecordKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tid" => Ok(RecordKey::Tid),
+            "nsid" => Ok(RecordKey::Nsid),
+            "any" => Ok(RecordKey::Any),
+            other => match other.strip_prefix("literal:") {
+                Some(value) => {
+                    is_record_key(value)?;
+                    Ok(RecordKey::Literal(value.to_string()))
+                }
+                None => Err(format!(
+                    "unknown record key type `{other}`, expected tid, nsid, any or literal:<value>"
+                )),
+            },
+        }
+    }
+}
+
+impl Display for RecordKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordKey::Tid => write!(f, "tid"),
+            RecordKey::Nsid => write!(f, "nsid"),
+            RecordKey::Any => write!(f, "any"),
+            RecordKey::Literal(value) => write!(f, "literal:{value}"),
+        }
+    }
+}
+
+impl Serialize for RecordKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for RecordKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+// 1 to 512 characters out of A-Za-z0-9.-_:~, but not `.` or `..`
+fn is_record_key(s: &str) -> Result<(), String> {
+    if s.is_empty() || s.len() > 512 {
+        return Err("record keys must be 1 to 512 characters long".to_string());
+    }
+    if s == "." || s == ".." {
+        return Err(format!("`{s}` isn't a valid record key"));
+    }
+    match s
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && !".-_:~".contains(*c))
+    {
+        Some(c) => Err(format!("`{c}` isn't allowed in record keys")),
+        None => Ok(()),
+    }
+}
+
+// 13 characters of base32-sortable, the first one keeps the top bit clear
+fn is_tid(s: &str) -> Result<(), String> {
+    const ALPHABET: &str = "234567abcdefghijklmnopqrstuvwxyz";
+    let valid = s.len() == 13
+        && s.chars().all(|c| ALPHABET.contains(c))
+        && s.starts_with(|c| "234567abcdefghij".contains(c));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("`{s}` isn't a valid tid"))
+    }
+}
+
+// a reversed domain name followed by a name segment, e.g. `com.example.fooBar`
+fn is_nsid(s: &str) -> Result<(), String> {
+    let segments: Vec<&str> = s.split('.').collect();
+    let invalid = || Err(format!("`{s}` isn't a valid nsid"));
+    if s.len() > 317 || segments.len() < 3 {
+        return invalid();
+    }
+    let (name, domain) = segments.split_last().unwrap();
+    let domain_ok = domain.iter().enumerate().all(|(i, x)| {
+        !x.is_empty()
+            && x.len() <= 63
+            && x.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            && !x.starts_with('-')
+            && !x.ends_with('-')
+            && (i > 0 || !x.starts_with(|c: char| c.is_ascii_digit()))
+    });
+    let name_ok = !name.is_empty()
+        && name.len() <= 63
+        && name.chars().all(|c| c.is_ascii_alphanumeric())
+        && !name.starts_with(|c: char| c.is_ascii_digit());
+    if domain_ok && name_ok {
+        Ok(())
+    } else {
+        invalid()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for s in ["tid", "nsid", "any", "literal:self"] {
+            let key: RecordKey = serde_json::from_str(&format!("\"{s}\"")).unwrap();
+            assert_eq!(serde_json::to_string(&key).unwrap(), format!("\"{s}\""));
+        }
+        assert_eq!(
+            "literal:self".parse(),
+            Ok(RecordKey::Literal("self".to_string()))
+        );
+        assert!("literal:".parse::<RecordKey>().is_err());
+        assert!("literal:a/b".parse::<RecordKey>().is_err());
+        assert!("uuid".parse::<RecordKey>().is_err());
+    }
+
+    #[test]
+    fn check() {
+        assert!(RecordKey::Tid.check("3jzfcijpj2z2a").is_ok());
+        assert!(RecordKey::Tid.check("self").is_err());
+        assert!(RecordKey::Tid.check("zjzfcijpj2z2a").is_err());
+        assert!(RecordKey::Nsid.check("com.example.fooBar").is_ok());
+        assert!(RecordKey::Nsid.check("com.example").is_err());
+        assert!(RecordKey::Any.check("anything-goes:~").is_ok());
+        assert!(RecordKey::Any.check("..").is_err());
+        assert!(RecordKey::Any.check("a b").is_err());
+        let literal = RecordKey::Literal("self".to_string());
+        assert!(literal.check("self").is_ok());
+        assert!(literal.check("other").is_err());
+    }
+}
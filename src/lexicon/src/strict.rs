@@ -0,0 +1,533 @@
+use std::fmt::Display;
+
+use serde_json::{Map, Value};
+
+use crate::{Lexicon, MAX_SAFE_INTEGER, RecordKey, StringFormats};
+
+/// a problem found while reading a lexicon strictly, `path` points at the offending value (e.g. `defs.main.record.properties.tags.maxLength`)
+#[derive(Debug, PartialEq, Eq)]
+pub struct StrictError {
+    pub path: String,
+    pub message: String,
+}
+
+impl Display for StrictError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for StrictError {}
+
+impl Lexicon {
+    /// parses a lexicon, rejecting unknown keys and mistyped values that serde would drop or stop at
+    /// every problem in the file is reported, not just the first one
+    pub fn from_str_strict(src: &str) -> Result<Lexicon, Vec<StrictError>> {
+        let value: Value = serde_json::from_str(src).map_err(|e| vec![error("", e)])?;
+        let mut checker = Checker { errors: Vec::new() };
+        checker.lexicon(&value);
+        if !checker.errors.is_empty() {
+            return Err(checker.errors);
+        }
+        serde_json::from_value(value).map_err(|e| vec![error("", e)])
+    }
+}
+
+fn error(path: &str, message: impl ToString) -> StrictError {
+    StrictError {
+        path: path.to_string(),
+        message: message.to_string(),
+    }
+}
+
+// what a value is supposed to be
+#[derive(Clone, Copy)]
+enum Expect {
+    String,
+    Integer,
+    Unsigned,
+    Boolean,
+    Strings,
+    Integers,
+    Format,
+    // a typed definition, nested ones follow the context of their parent
+    Type,
+    // name -> field type
+    Properties,
+    // name -> parameter type
+    ParamProperties,
+    // `parameters` of an xrpc def
+    Params,
+    // `record` of a record def
+    Object,
+    Io,
+    Schema,
+    Errors,
+    Message,
+    Union,
+    RecordKey,
+    // name -> top level definition
+    Defs,
+}
+
+// where a type appears, decides which kinds are allowed
+#[derive(Clone, Copy, PartialEq)]
+enum Context {
+    Def,
+    Field,
+    Param,
+}
+
+type Fields = &'static [(&'static str, Expect, bool)];
+
+// keys every typed value accepts
+const COMMON: Fields = &[
+    ("type", Expect::String, true),
+    ("description", Expect::String, false),
+];
+
+const PRIMARY: &[&str] = &["record", "query", "procedure", "subscription"];
+const PARAM: &[&str] = &["boolean", "integer", "string", "unknown", "array"];
+
+// keys each type accepts besides `type` and `description`, and whether they're required
+fn fields(kind: &str) -> Option<Fields> {
+    use Expect::*;
+    Some(match kind {
+        "null" | "cid-link" | "token" | "unknown" => &[],
+        "boolean" => &[("default", Boolean, false), ("const", Boolean, false)],
+        "integer" => &[
+            ("minimum", Integer, false),
+            ("maximum", Integer, false),
+            ("enum", Integers, false),
+            ("default", Integer, false),
+            ("const", Integer, false),
+        ],
+        "string" => &[
+            ("format", Format, false),
+            ("maxLength", Unsigned, false),
+            ("minLength", Unsigned, false),
+            ("maxGraphemes", Unsigned, false),
+            ("minGraphemes", Unsigned, false),
+            ("knownValues", Strings, false),
+            ("enum", Strings, false),
+            ("default", String, false),
+            ("const", String, false),
+        ],
+        "bytes" => &[
+            ("minLength", Unsigned, false),
+            ("maxLength", Unsigned, false),
+        ],
+        "blob" => &[("accept", Strings, false), ("maxSize", Unsigned, false)],
+        "array" => &[
+            ("items", Type, true),
+            ("minLength", Unsigned, false),
+            ("maxLength", Unsigned, false),
+        ],
+        "object" => &[
+            ("properties", Properties, true),
+            ("required", Strings, false),
+            ("nullable", Strings, false),
+        ],
+        "params" => &[
+            ("required", Strings, false),
+            ("properties", ParamProperties, true),
+        ],
+        "ref" => &[("ref", String, true)],
+        "union" => &[("refs", Strings, true), ("closed", Boolean, false)],
+        "record" => &[("key", RecordKey, true), ("record", Object, true)],
+        "query" => &[
+            ("parameters", Params, false),
+            ("output", Io, false),
+            ("errors", Errors, false),
+        ],
+        "procedure" => &[
+            ("parameters", Params, false),
+            ("input", Io, false),
+            ("output", Io, false),
+            ("errors", Errors, false),
+        ],
+        "subscription" => &[
+            ("parameters", Params, false),
+            ("message", Message, true),
+            ("errors", Errors, false),
+        ],
+        _ => return None,
+    })
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+fn describe(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(x) if x.is_f64() => "a float",
+        Value::Number(_) => "an integer",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+struct Checker {
+    errors: Vec<StrictError>,
+}
+
+impl Checker {
+    fn mismatch(&mut self, path: &str, expected: &str, found: &Value) {
+        self.errors.push(error(
+            path,
+            format!("expected {expected}, found {}", describe(found)),
+        ));
+    }
+
+    fn object<'v>(&mut self, path: &str, value: &'v Value) -> Option<&'v Map<String, Value>> {
+        match value {
+            Value::Object(x) => Some(x),
+            other => {
+                self.mismatch(path, "an object", other);
+                None
+            }
+        }
+    }
+
+    fn lexicon(&mut self, value: &Value) {
+        use Expect::*;
+        let Some(map) = self.object("", value) else {
+            return;
+        };
+        if let Some(version) = map.get("lexicon")
+            && version.as_i64().is_some_and(|x| x != 1)
+        {
+            self.errors
+                .push(error("lexicon", "only lexicon version 1 is supported"));
+        }
+        self.fields(
+            "",
+            map,
+            &[
+                ("$schema", String, false),
+                ("lexicon", Integer, true),
+                ("id", String, true),
+                ("revision", Integer, false),
+                ("description", String, false),
+                ("defs", Defs, true),
+            ],
+            Context::Field,
+            None,
+        );
+    }
+
+    // checks every key of an object against `fields`, `kind` is set for typed values
+    fn fields(
+        &mut self,
+        path: &str,
+        map: &Map<String, Value>,
+        fields: Fields,
+        context: Context,
+        kind: Option<&str>,
+    ) {
+        let common = if kind.is_some() { COMMON } else { &[] };
+        for (key, value) in map {
+            let path = join(path, key);
+            match fields.iter().chain(common).find(|(name, _, _)| name == key) {
+                Some((_, expect, _)) => self.value(&path, value, *expect, context),
+                None => {
+                    let message = match kind {
+                        Some(kind) => format!("unknown key `{key}` for `{kind}`"),
+                        None => format!("unknown key `{key}`"),
+                    };
+                    self.errors.push(error(&path, message));
+                }
+            }
+        }
+        for (name, _, required) in fields {
+            if *required && !map.contains_key(*name) {
+                self.errors
+                    .push(error(path, format!("missing required key `{name}`")));
+            }
+        }
+    }
+
+    // a value tagged with `type`, `only` restricts which kinds are allowed
+    fn typed(&mut self, path: &str, value: &Value, context: Context, only: Option<&[&str]>) {
+        let Some(map) = self.object(path, value) else {
+            return;
+        };
+        let kind = match map.get("type") {
+            Some(Value::String(kind)) => kind.as_str(),
+            Some(other) => return self.mismatch(&join(path, "type"), "a string", other),
+            None => {
+                return self.errors.push(error(path, "missing required key `type`"));
+            }
+        };
+        let Some(fields) = fields(kind) else {
+            return self
+                .errors
+                .push(error(&join(path, "type"), format!("unknown type `{kind}`")));
+        };
+
+        if let Some(only) = only.filter(|x| !x.contains(&kind)) {
+            self.errors.push(error(
+                path,
+                format!("expected {}, found `{kind}`", only.join(" or ")),
+            ));
+        } else if context != Context::Def && PRIMARY.contains(&kind) {
+            self.errors.push(error(
+                path,
+                format!("`{kind}` can only be used as a top level definition"),
+            ));
+        } else if context == Context::Param && !PARAM.contains(&kind) {
+            self.errors
+                .push(error(path, format!("parameters can't be of type `{kind}`")));
+        }
+
+        // nested types stay params inside params, everything else is a field
+        let inner = if context == Context::Param {
+            Context::Param
+        } else {
+            Context::Field
+        };
+        self.fields(path, map, fields, inner, Some(kind));
+    }
+
+    fn value(&mut self, path: &str, value: &Value, expect: Expect, context: Context) {
+        match expect {
+            Expect::String if !value.is_string() => self.mismatch(path, "a string", value),
+            Expect::Integer if value.as_i64().is_none() && !value.is_u64() => {
+                self.mismatch(path, "an integer", value)
+            }
+            Expect::Integer
+                if value
+                    .as_i64()
+                    .is_none_or(|x| !(-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(&x)) =>
+            {
+                self.errors.push(error(
+                    path,
+                    format!("{value} is outside of the safe integer range, ±2^53-1"),
+                ))
+            }
+            Expect::Unsigned if value.as_u64().is_none_or(|x| u32::try_from(x).is_err()) => {
+                self.mismatch(path, "an unsigned 32 bit integer", value)
+            }
+            Expect::Boolean if !value.is_boolean() => self.mismatch(path, "a boolean", value),
+            Expect::Strings | Expect::Integers => {
+                let (item, name) = match expect {
+                    Expect::Strings => (Expect::String, "an array of strings"),
+                    _ => (Expect::Integer, "an array of integers"),
+                };
+                match value {
+                    Value::Array(values) => {
+                        for (i, value) in values.iter().enumerate() {
+                            self.value(&format!("{path}[{i}]"), value, item, context);
+                        }
+                    }
+                    other => self.mismatch(path, name, other),
+                }
+            }
+            Expect::RecordKey => match value {
+                Value::String(key) => {
+                    if let Err(e) = key.parse::<RecordKey>() {
+                        self.errors.push(error(path, e));
+                    }
+                }
+                other => self.mismatch(path, "a string", other),
+            },
+            Expect::Format => match value {
+                Value::String(format) if StringFormats::from_str(format).is_none() => self
+                    .errors
+                    .push(error(path, format!("unknown string format `{format}`"))),
+                Value::String(_) => {}
+                other => self.mismatch(path, "a string", other),
+            },
+            Expect::Type => self.typed(path, value, context, None),
+            Expect::Properties | Expect::ParamProperties | Expect::Defs => {
+                let context = match expect {
+                    Expect::Properties => Context::Field,
+                    Expect::ParamProperties => Context::Param,
+                    _ => Context::Def,
+                };
+                if let Some(map) = self.object(path, value) {
+                    for (name, value) in map {
+                        self.typed(&join(path, name), value, context, None);
+                    }
+                }
+            }
+            Expect::Params => self.typed(path, value, Context::Field, Some(&["params"])),
+            Expect::Object => self.typed(path, value, Context::Field, Some(&["object"])),
+            Expect::Schema => self.typed(
+                path,
+                value,
+                Context::Field,
+                Some(&["object", "ref", "union"]),
+            ),
+            Expect::Union => self.typed(path, value, Context::Field, Some(&["union"])),
+            Expect::Io => {
+                if let Some(map) = self.object(path, value) {
+                    self.fields(
+                        path,
+                        map,
+                        &[
+                            ("description", Expect::String, false),
+                            ("encoding", Expect::String, true),
+                            ("schema", Expect::Schema, false),
+                        ],
+                        Context::Field,
+                        None,
+                    );
+                }
+            }
+            Expect::Errors => match value {
+                Value::Array(errors) => {
+                    for (i, value) in errors.iter().enumerate() {
+                        let path = format!("{path}[{i}]");
+                        if let Some(map) = self.object(&path, value) {
+                            self.fields(
+                                &path,
+                                map,
+                                &[
+                                    ("name", Expect::String, true),
+                                    ("description", Expect::String, false),
+                                ],
+                                Context::Field,
+                                None,
+                            );
+                        }
+                    }
+                }
+                other => self.mismatch(path, "an array of errors", other),
+            },
+            Expect::Message => {
+                if let Some(map) = self.object(path, value) {
+                    self.fields(
+                        path,
+                        map,
+                        &[
+                            ("description", Expect::String, false),
+                            ("schema", Expect::Union, true),
+                        ],
+                        Context::Field,
+                        None,
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(src: &str) -> Vec<String> {
+        Lexicon::from_str_strict(src)
+            .err()
+            .unwrap_or_default()
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn valid() {
+        let src = std::fs::read_to_string("../../pinksea.json").unwrap();
+        assert_eq!(errors(&src), Vec::<String>::new());
+        for (path, src) in crate::STD {
+            assert_eq!(errors(src), Vec::<String>::new(), "{path}");
+        }
+    }
+
+    #[test]
+    fn mistyped() {
+        // oekaki.json is a single record def
+        let def = std::fs::read_to_string("../../oekaki.json").unwrap();
+        let src = format!(
+            r#"{{ "lexicon": 1, "id": "com.shinolabs.pinksea.oekaki", "defs": {{ "main": {def} }} }}"#
+        );
+        assert_eq!(
+            errors(&src),
+            vec![
+                "defs.main.record.properties.tags.items.maxLength: expected an unsigned 32 bit integer, found a string"
+            ]
+        );
+    }
+
+    #[test]
+    fn large_integers() {
+        let src = r#"{ "lexicon": 1, "id": "com.example.test", "defs": {
+            "main": { "type": "integer", "minimum": 0, "maximum": 9007199254740991 },
+            "other": { "type": "integer", "default": 9007199254740992 }
+        } }"#;
+        assert_eq!(
+            errors(src),
+            vec![
+                "defs.other.default: 9007199254740992 is outside of the safe integer range, ±2^53-1"
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_keys() {
+        let src = r#"{
+            "lexicon": 1,
+            "id": "com.example.test",
+            "extra": true,
+            "defs": {
+                "main": { "type": "string", "maxLenght": 10 },
+                "other": {
+                    "type": "object",
+                    "properties": { "a": { "type": "blob", "accept": ["image/*"], "size": 5 } }
+                }
+            }
+        }"#;
+        assert_eq!(
+            errors(src),
+            vec![
+                "extra: unknown key `extra`",
+                "defs.main.maxLenght: unknown key `maxLenght` for `string`",
+                "defs.other.properties.a.size: unknown key `size` for `blob`",
+            ]
+        );
+    }
+
+    #[test]
+    fn misplaced() {
+        let src = r#"{
+            "lexicon": 1,
+            "id": "com.example.test",
+            "defs": {
+                "main": {
+                    "type": "query",
+                    "parameters": {
+                        "type": "params",
+                        "properties": { "a": { "type": "object", "properties": {} } }
+                    }
+                },
+                "other": {
+                    "type": "array",
+                    "items": { "type": "record", "key": "tid", "record": { "type": "object", "properties": {} } }
+                },
+                "missing": { "type": "ref" }
+            }
+        }"#;
+        assert_eq!(
+            errors(src),
+            vec![
+                "defs.main.parameters.properties.a: parameters can't be of type `object`",
+                "defs.other.items: `record` can only be used as a top level definition",
+                "defs.missing: missing required key `ref`",
+            ]
+        );
+    }
+}
@@ -1,154 +1,558 @@
-use std::{collections::HashMap, fs};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
 
 use clap::Parser as ArgParser;
-use lexicon::{
-    AtpArray, AtpBlob, AtpBoolean, AtpBytes, AtpCidLink, AtpInteger, AtpNull, AtpObject, AtpParams,
-    AtpProcedure, AtpQuery, AtpRecord, AtpRef, AtpString, AtpSubscription, AtpToken, AtpTypes,
-    AtpUnion, AtpUnknown, Lexicon, StringFormats, atp_format,
-};
+use ir::diagnostic::{Diagnostic, has_errors};
+use lexicon::{Catalog, CatalogError, Lexicon};
+use serde_json::json;
+use tree_sitter::Parser;
 
-use tree_sitter::{InputEdit, Language, Node, Parser, Point, TreeCursor};
+mod codegen;
+mod compile;
+mod diff;
+mod docs;
+mod error_sets;
+mod fmt;
+mod import;
+mod pagination;
+mod revision;
+mod spread;
+mod watch;
+
+// exit codes: 0 ok, 1 the input has problems (errors, unformatted files, changes), 2 the tool couldn't run
+const FAILURE: u8 = 1;
+const TROUBLE: u8 = 2;
 
 #[derive(clap::Parser, Debug)]
+#[command(version, about = "tooling for atproto lexicons")]
 struct Args {
-    path: String,
+    #[command(subcommand)]
+    command: Command,
+    /// only print diagnostics
+    #[arg(long, short, global = true)]
+    quiet: bool,
+    /// how diagnostics are printed
+    #[arg(long, value_enum, default_value_t = MessageFormat::Human, global = true)]
+    message_format: MessageFormat,
+    /// directories of json lexicons that refs are checked against, on top of the bundled com.atproto ones
+    #[arg(long, global = true)]
+    lexicons: Vec<PathBuf>,
+    /// reject unknown keys and mistyped values in json lexicons instead of ignoring them
+    #[arg(long, global = true)]
+    strict: bool,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// compile ana files to lexicon json
+    Build {
+        /// files or directories of .ana files
+        paths: Vec<PathBuf>,
+        #[command(flatten)]
+        output: Output,
+        /// also dump internal representations to stdout
+        #[arg(long, value_enum, value_delimiter = ',')]
+        emit: Vec<Emit>,
+        /// keep running and rebuild whenever a source file changes
+        #[arg(long)]
+        watch: bool,
+    },
+    /// check ana files for errors without writing anything
+    Check {
+        /// files or directories of .ana files
+        paths: Vec<PathBuf>,
+    },
+    /// format ana files in place, or stdin to stdout when no paths are given
+    Fmt {
+        paths: Vec<PathBuf>,
+        /// don't write anything, fail if any file isn't formatted
+        #[arg(long)]
+        check: bool,
+    },
+    /// convert lexicon json files to ana
+    Import {
+        /// files or directories of .json lexicons
+        paths: Vec<PathBuf>,
+        /// write results into this directory instead of stdout
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+    /// generate typescript types
+    Codegen {
+        /// ana files or json lexicons
+        paths: Vec<PathBuf>,
+        /// write results into this directory instead of stdout
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+    /// generate markdown documentation
+    Docs {
+        /// ana files or json lexicons
+        paths: Vec<PathBuf>,
+        /// write results into this directory instead of stdout
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+    /// compare two revisions of a lexicon, either may be ana or json
+    /// fails when the new revision breaks existing records or clients
+    Diff { old: PathBuf, new: PathBuf },
+}
+
+#[derive(clap::Args, Debug)]
+struct Output {
+    /// write results into this directory instead of stdout
+    #[arg(long)]
+    out_dir: Option<PathBuf>,
+    /// json output style
+    #[arg(long, value_enum, default_value_t = OutputFormat::Pretty)]
+    format: OutputFormat,
+    /// directory of previously published lexicons that revisions are counted against, defaults to the out dir
+    #[arg(long)]
+    published: Option<PathBuf>,
+    /// publish changes that break existing records or clients
+    #[arg(long)]
+    allow_breaking: bool,
 }
 
-fn main() {
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum MessageFormat {
+    Human,
+    Json,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    Json,
+    Pretty,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum Emit {
+    Cst,
+    Ir,
+    Lexicon,
+}
+
+fn main() -> ExitCode {
     let args = Args::parse();
+    let mut cli = Cli {
+        quiet: args.quiet,
+        message_format: args.message_format,
+        code: 0,
+        catalog: Catalog::with_std(),
+        strict: args.strict,
+        published: HashMap::new(),
+    };
+    for dir in &args.lexicons {
+        for e in cli.catalog.load_dir(dir) {
+            cli.catalog_error(e);
+        }
+    }
+
+    match args.command {
+        Command::Build {
+            paths,
+            output,
+            emit,
+            watch: true,
+        } => watch::watch(&mut cli, &paths, &output, &emit),
+        Command::Build {
+            paths,
+            output,
+            emit,
+            watch: false,
+        } => cli.build(&paths, Some(&output), &emit),
+        Command::Check { paths } => cli.build(&paths, None, &[]),
+        Command::Fmt { paths, check } => cli.format(&paths, check),
+        Command::Import { paths, out_dir } => {
+            for path in cli.sources(&paths, &["json"]) {
+                if let Some(lexicon) = cli.load(&path) {
+                    let file = format!("{}.ana", lexicon.id.replace('.', "/"));
+                    cli.write(out_dir.as_deref(), &file, &import::import(&lexicon));
+                }
+            }
+        }
+        Command::Codegen { paths, out_dir } => {
+            // lexicons generated together can import each other
+            let mut lexicons = Vec::new();
+            for path in cli.sources(&paths, &["ana", "json"]) {
+                let Some(lexicon) = cli.load(&path) else {
+                    continue;
+                };
+                // the same lexicon from --lexicons is fine, a different one is a conflict
+                if cli.catalog.get(&lexicon.id) != Some(&lexicon)
+                    && let Err(e) = cli.catalog.insert(&path, lexicon.clone())
+                {
+                    cli.catalog_error(e);
+                    continue;
+                }
+                lexicons.push((path, lexicon));
+            }
+            for (path, lexicon) in lexicons {
+                let file = format!("{}.ts", lexicon.id.replace('.', "/"));
+                match codegen::typescript(&lexicon, &cli.catalog) {
+                    Ok(out) => cli.write(out_dir.as_deref(), &file, &out),
+                    Err(errors) => {
+                        for e in errors {
+                            cli.error(&path, &e, FAILURE);
+                        }
+                    }
+                }
+            }
+        }
+        Command::Docs { paths, out_dir } => {
+            for lexicon in cli.load_all(&paths) {
+                let file = format!("{}.md", lexicon.id.replace('.', "/"));
+                cli.write(out_dir.as_deref(), &file, &docs::markdown(&lexicon));
+            }
+        }
+        Command::Diff { old, new } => {
+            if let (Some(old), Some(new)) = (cli.load(&old), cli.load(&new)) {
+                let changes = diff::diff(&old, &new);
+                cli.print(&diff::report(&old, &changes));
+                if diff::is_breaking(&changes) {
+                    cli.fail(FAILURE);
+                }
+            }
+        }
+    }
 
-    let src = fs::read_to_string(&args.path).unwrap();
+    ExitCode::from(cli.code)
+}
 
-    let mut parser = Parser::new();
-    parser
-        .set_language(&tree_sitter_ana::LANGUAGE.into())
-        .expect("Error loading ana grammar");
+struct Cli {
+    quiet: bool,
+    message_format: MessageFormat,
+    code: u8,
+    catalog: Catalog,
+    strict: bool,
+    // published lexicons as they were the first time they were read, keyed by path
+    // under --watch every save is counted against these rather than the previous save
+    published: HashMap<PathBuf, Option<Lexicon>>,
+}
 
-    let mut tree = parser.parse(&src, None).unwrap();
-    println!("{}", tree.root_node().to_sexp());
+impl Cli {
+    fn fail(&mut self, code: u8) {
+        self.code = self.code.max(code);
+    }
 
-    let mut cursor = tree.walk();
+    fn info(&self, message: &str) {
+        if !self.quiet {
+            eprintln!("{message}");
+        }
+    }
 
-    // only one namespace supported for now
-    let mut namespace = tree
-        .root_node()
-        .child(0)
-        .expect("you sure this is the right file?");
-    println!("tree: {}", namespace.to_sexp());
+    // findings that go to stdout, with --quiet the exit code tells them
+    fn print(&self, text: &str) {
+        if !self.quiet {
+            print!("{text}");
+        }
+    }
 
-    let nsid = namespace.child_by_field_name("name").unwrap();
-    let nsid = &src[nsid.byte_range()];
-    println!("nsid: {}", nsid);
+    // io errors and the like, the tool couldn't do its job
+    fn trouble(&mut self, path: &Path, message: &str) {
+        self.error(path, message, TROUBLE);
+    }
 
-    let mut res = Lexicon {
-        lexicon: 1,
-        id: nsid.to_string(),
-        revision: None,
-        description: None,
-        defs: HashMap::new(),
-    };
+    // problems that aren't tied to a location in a source file
+    fn error(&mut self, path: &Path, message: &str, code: u8) {
+        match self.message_format {
+            MessageFormat::Human => eprintln!("{}: error: {message}", path.display()),
+            MessageFormat::Json => println!(
+                "{}",
+                json!({ "file": path, "severity": "error", "message": message })
+            ),
+        }
+        self.fail(code);
+    }
 
-    let body = namespace.children_by_field_name("body", &mut cursor);
-
-    for def in body {
-        println!("a: {}", def.to_sexp());
-        match def.kind() {
-            "record" => panic!("found record!"),
-            "object" => {
-                let name = &src[def.child_by_field_name("name").unwrap().byte_range()];
-                let mut object = AtpObject {
-                    description: None,
-                    properties: HashMap::new(),
-                    required: None,
-                    nullable: None,
-                };
+    fn catalog_error(&mut self, e: CatalogError) {
+        match e {
+            CatalogError::Io { path, message } => self.trouble(&path, &message),
+            CatalogError::Parse {
+                path,
+                line,
+                column,
+                message,
+            } => self.error(&path, &format!("{line}:{column}: {message}"), FAILURE),
+            CatalogError::Duplicate {
+                nsid,
+                first,
+                second,
+            } => self.error(
+                &second,
+                &format!("{nsid} is already defined in {}", first.display()),
+                FAILURE,
+            ),
+        }
+    }
 
-                let mut required: Vec<String> = Vec::new();
-
-                let mut cursor = def.walk();
-                let props = def
-                    .child_by_field_name("body")
-                    .unwrap()
-                    .children(&mut cursor);
-                for prop in props {
-                    println!("{}", prop.to_sexp());
-                    match prop.kind() {
-                        "property" => println!("found property!"),
-                        "optional" => {
-                            println!("found optional prop!");
-                            required.push(
-                                (&src[prop.child_by_field_name("name").unwrap().byte_range()])
-                                    .to_string(),
-                            );
-                        }
-                        "ref" => println!("found ref!"),
-                        _ => continue,
-                    };
-
-                    let name = &src[prop.child_by_field_name("name").unwrap().byte_range()];
-                    let typen = prop.child_by_field_name("type").unwrap();
-                    let mut atp_type = AtpTypes::Unknown(AtpUnknown::new());
-                    match typen.kind() {
-                        "ref" => {
-                            println!("{:?}", typen.to_sexp());
-                            atp_type = AtpTypes::Ref(AtpRef {
-                                description: None,
-                                reference: String::from(
-                                    &src[typen.child_by_field_name("id").unwrap().byte_range()],
-                                ),
-                            });
-                        }
-                        "type" => {
-                            let name =
-                                &src[typen.child_by_field_name("name").unwrap().byte_range()];
-                            atp_type = match name {
-                                "Null" => AtpTypes::Null(AtpNull::new()),
-                                "Boolean" => AtpTypes::Boolean(AtpBoolean::new()),
-                                "Integer" => AtpTypes::Integer(AtpInteger::new()),
-                                "String" => AtpTypes::String(AtpString::new()),
-                                "Bytes" => AtpTypes::Bytes(AtpBytes::new()),
-                                "CidLink" => AtpTypes::CidLink(AtpCidLink::new()),
-                                "Blob" => AtpTypes::Blob(AtpBlob::new()),
-                                "Array" => AtpTypes::Array(AtpArray::new()),
-                                "Object" => AtpTypes::Object(AtpObject::new()),
-                                "Params" => AtpTypes::Params(AtpParams::new()),
-                                "Token" => AtpTypes::Token(AtpToken::new()),
-                                "Ref" => AtpTypes::Ref(AtpRef::new()),
-                                "Union" => AtpTypes::Union(AtpUnion::new()),
-                                "Unknown" => AtpTypes::Unknown(AtpUnknown::new()),
-                                "Record" => AtpTypes::Record(AtpRecord::new()),
-                                "Query" => AtpTypes::Query(AtpQuery::new()),
-                                "Procedure" => AtpTypes::Procedure(AtpProcedure::new()),
-                                "Subscription" => AtpTypes::Subscription(AtpSubscription::new()),
-                                "AtIdentifier" => atp_format(StringFormats::AtIdentifier),
-                                "AtUri" => atp_format(StringFormats::AtUri),
-                                "Cid" => atp_format(StringFormats::Cid),
-                                "DateTime" => atp_format(StringFormats::Datetime),
-                                "Did" => atp_format(StringFormats::Did),
-                                "Handle" => atp_format(StringFormats::Handle),
-                                "Nsid" => atp_format(StringFormats::Nsid),
-                                "Tid" => atp_format(StringFormats::Tid),
-                                "RecordKey" => atp_format(StringFormats::RecordKey),
-                                "Uri" => atp_format(StringFormats::Uri),
-                                "Language" => atp_format(StringFormats::Language),
-                                _ => panic!("unknown type {name}"),
-                            };
+    fn report(&mut self, path: &Path, diagnostics: &[Diagnostic]) {
+        for d in diagnostics {
+            let start = d.loc.start_point;
+            let end = d.loc.end_point;
+            match self.message_format {
+                MessageFormat::Human => eprintln!(
+                    "{}:{}:{}: {}: {}",
+                    path.display(),
+                    start.row + 1,
+                    start.column + 1,
+                    d.severity,
+                    d.message
+                ),
+                MessageFormat::Json => println!(
+                    "{}",
+                    json!({
+                        "file": path,
+                        "severity": d.severity.to_string(),
+                        "message": d.message,
+                        "range": {
+                            "start": { "line": start.row, "column": start.column, "byte": d.loc.start_byte },
+                            "end": { "line": end.row, "column": end.column, "byte": d.loc.end_byte },
+                        },
+                    })
+                ),
+            }
+        }
+        if has_errors(diagnostics) {
+            self.fail(FAILURE);
+        }
+    }
+
+    // expands directories into the files with the given extensions below them
+    fn sources(&mut self, paths: &[PathBuf], extensions: &[&str]) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        let mut stack: Vec<PathBuf> = paths.iter().rev().cloned().collect();
+        while let Some(path) = stack.pop() {
+            if !path.is_dir() {
+                files.push(path);
+                continue;
+            }
+            match fs::read_dir(&path) {
+                Ok(entries) => {
+                    let mut entries: Vec<PathBuf> =
+                        entries.filter_map(|x| x.ok()).map(|x| x.path()).collect();
+                    entries.sort();
+                    for entry in entries.into_iter().rev() {
+                        if entry.is_dir()
+                            || entry
+                                .extension()
+                                .is_some_and(|x| extensions.iter().any(|e| x == *e))
+                        {
+                            stack.push(entry);
                         }
-                        "array" => todo!(),
-                        "union" => todo!(),
-                        _ => panic!("unknown type {name}"),
                     }
-                    object.properties.insert(name.to_string(), atp_type);
                 }
+                Err(e) => self.trouble(&path, &e.to_string()),
+            }
+        }
+        files
+    }
+
+    fn read(&mut self, path: &Path) -> Option<String> {
+        match fs::read_to_string(path) {
+            Ok(src) => Some(src),
+            Err(e) => {
+                self.trouble(path, &e.to_string());
+                None
+            }
+        }
+    }
+
+    fn write(&mut self, out_dir: Option<&Path>, file: &str, contents: &str) {
+        let Some(out_dir) = out_dir else {
+            print!("{contents}");
+            return;
+        };
+        let path = out_dir.join(file);
+        let written = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&path, contents));
+        match written {
+            Ok(_) => self.info(&format!("wrote {}", path.display())),
+            Err(e) => self.trouble(&path, &e.to_string()),
+        }
+    }
+
+    // reads a lexicon from either a json file or an ana source
+    fn load(&mut self, path: &Path) -> Option<Lexicon> {
+        let src = self.read(path)?;
+        if path.extension().is_some_and(|x| x == "json") && self.strict {
+            return match Lexicon::from_str_strict(&src) {
+                Ok(lexicon) => Some(lexicon),
+                Err(errors) => {
+                    for e in errors {
+                        self.error(path, &e.to_string(), FAILURE);
+                    }
+                    None
+                }
+            };
+        }
+        if path.extension().is_some_and(|x| x == "json") {
+            return match serde_json::from_str(&src) {
+                Ok(lexicon) => Some(lexicon),
+                Err(e) => {
+                    self.error(path, &e.to_string(), FAILURE);
+                    None
+                }
+            };
+        }
+
+        let tree = parser().parse(&src, None).unwrap();
+        let (lexicon, diagnostics) = compile::compile(&src, &tree, &self.catalog);
+        self.report(path, &diagnostics);
+        (!has_errors(&diagnostics)).then_some(lexicon)
+    }
+
+    fn load_all(&mut self, paths: &[PathBuf]) -> Vec<Lexicon> {
+        self.sources(paths, &["ana", "json"])
+            .iter()
+            .filter_map(|x| self.load(x))
+            .collect()
+    }
+
+    fn build(&mut self, paths: &[PathBuf], output: Option<&Output>, emit: &[Emit]) {
+        for path in self.sources(paths, &["ana"]) {
+            self.build_file(&path, output, emit);
+        }
+    }
+
+    // compiles one file and writes it out if it has no errors
+    // the lexicon is returned even when it has errors, it's the best guess at what the file declares
+    fn build_file(
+        &mut self,
+        path: &Path,
+        output: Option<&Output>,
+        emit: &[Emit],
+    ) -> Option<Lexicon> {
+        let src = self.read(path)?;
+        let tree = parser().parse(&src, None).unwrap();
+        if emit.contains(&Emit::Cst) {
+            println!("{}", tree.root_node().to_sexp());
+        }
+        if emit.contains(&Emit::Ir) {
+            println!("{:#?}", ir::document::Document::new(&src).defs);
+        }
+
+        let (mut lexicon, diagnostics) = compile::compile(&src, &tree, &self.catalog);
+        self.report(path, &diagnostics);
+        if emit.contains(&Emit::Lexicon) {
+            println!("{:#?}", lexicon);
+        }
+
+        if let Some(output) = output.filter(|_| !has_errors(&diagnostics)) {
+            let file = format!("{}.json", lexicon.id.replace('.', "/"));
+            if self.publish(path, output, &file, &mut lexicon).is_none() {
+                return Some(lexicon);
+            }
+            let json = match output.format {
+                OutputFormat::Json => serde_json::to_string(&lexicon),
+                OutputFormat::Pretty => serde_json::to_string_pretty(&lexicon),
+            };
+            self.write(output.out_dir.as_deref(), &file, &(json.unwrap() + "\n"));
+        }
+        Some(lexicon)
+    }
+
+    // counts the revision against the published lexicon and records the changes
+    // returns None when the lexicon must not be written
+    fn publish(
+        &mut self,
+        path: &Path,
+        output: &Output,
+        file: &str,
+        lexicon: &mut Lexicon,
+    ) -> Option<()> {
+        let Some(history) = output.published.as_deref().or(output.out_dir.as_deref()) else {
+            return Some(());
+        };
+        let published = history.join(file);
+        let published = match self.published.get(&published) {
+            Some(x) => x.clone(),
+            None => {
+                let lexicon = if published.exists() {
+                    // a published file we can't read would otherwise be silently overwritten
+                    Some(self.load(&published)?)
+                } else {
+                    None
+                };
+                self.published.insert(published, lexicon.clone());
+                lexicon
+            }
+        };
+
+        let changes = revision::bump(published.as_ref(), lexicon);
+        if diff::is_breaking(&changes) && !output.allow_breaking {
+            for change in changes
+                .iter()
+                .filter(|x| x.compat == diff::Compat::Breaking)
+            {
+                let message = format!(
+                    "breaking change to {}#{}: {}",
+                    lexicon.id, change.path, change.message
+                );
+                self.error(path, &message, FAILURE);
+            }
+            self.info("pass --allow-breaking to publish it anyway");
+            return None;
+        }
 
-                res.defs.insert(name.to_string(), AtpTypes::Object(object));
+        if let Some(out_dir) = output.out_dir.as_deref() {
+            let file = format!("{}.changelog.md", lexicon.id.replace('.', "/"));
+            let existing = fs::read_to_string(out_dir.join(&file)).ok();
+            let log = revision::changelog(existing.as_deref(), lexicon, &changes);
+            if existing.as_ref() != Some(&log) {
+                self.write(Some(out_dir), &file, &log);
             }
-            "get" => panic!("found get!"),
-            _ => panic!("unexpected type"),
         }
+        Some(())
     }
 
-    println!("res: {:#?}", res);
+    fn format(&mut self, paths: &[PathBuf], check: bool) {
+        if paths.is_empty() || paths == [PathBuf::from("-")] {
+            let mut src = String::new();
+            if let Err(e) = io::stdin().read_to_string(&mut src) {
+                return self.trouble(Path::new("<stdin>"), &e.to_string());
+            }
+            match fmt::format(&src) {
+                Ok(out) if check && out != src => self.fail(FAILURE),
+                Ok(_) if check => {}
+                Ok(out) => io::stdout().write_all(out.as_bytes()).unwrap(),
+                Err(e) => self.error(Path::new("<stdin>"), &e, FAILURE),
+            }
+            return;
+        }
+
+        for path in self.sources(paths, &["ana"]) {
+            let Some(src) = self.read(&path) else {
+                continue;
+            };
+            match fmt::format(&src) {
+                Ok(out) if out == src => {}
+                Ok(_) if check => {
+                    self.print(&format!("{} is not formatted\n", path.display()));
+                    self.fail(FAILURE);
+                }
+                Ok(out) => match fs::write(&path, out) {
+                    Ok(_) => self.info(&format!("formatted {}", path.display())),
+                    Err(e) => self.trouble(&path, &e.to_string()),
+                },
+                Err(e) => self.error(&path, &e, FAILURE),
+            }
+        }
+    }
+}
 
-    println!("{}", serde_json::to_string_pretty(&res).unwrap());
+fn parser() -> Parser {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&tree_sitter_ana::LANGUAGE.into())
+        .expect("Error loading ana grammar");
+    parser
 }
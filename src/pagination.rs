@@ -0,0 +1,240 @@
+use ir::{
+    diagnostic::Diagnostic,
+    props::{GenericProp, ParamType},
+};
+use lexicon::{
+    AtpInteger, AtpObject, AtpParams, AtpQuery, AtpString, AtpTypes, ParamProps, RpcIO, RpcSchema,
+};
+
+// the `limit` that `paginated` adds, `paginated(limit=1..50, default=25)` changes it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: i64,
+    pub max: i64,
+    pub default: i64,
+}
+
+impl Default for Bounds {
+    fn default() -> Self {
+        Bounds {
+            min: 1,
+            max: 100,
+            default: 50,
+        }
+    }
+}
+
+const PARAMS: &[(&str, ParamType)] =
+    &[("limit", ParamType::Slice), ("default", ParamType::Integer)];
+
+impl Bounds {
+    // the modifier in front of `query`, only `paginated` exists so far
+    pub fn parse(mut modifier: GenericProp) -> (Bounds, Vec<Diagnostic>) {
+        if modifier.name != "paginated" {
+            let message = format!(
+                "unknown modifier `{}`, queries can only be `paginated`",
+                modifier.name
+            );
+            return (
+                Bounds::default(),
+                vec![Diagnostic::error(message, modifier.loc)],
+            );
+        }
+        modifier.expect(PARAMS);
+        let mut bounds = Bounds::default();
+        if let Some(limit) = modifier.slice("limit") {
+            bounds.min = limit.start.unwrap_or(bounds.min);
+            bounds.max = limit.end.unwrap_or(bounds.max);
+        }
+        bounds.default = match modifier.integer("default") {
+            Some(default) => default,
+            None => bounds.default.clamp(bounds.min, bounds.max.max(bounds.min)),
+        };
+
+        let mut diagnostics = modifier.finish();
+        if bounds.min < 1 || bounds.max < bounds.min {
+            let message = format!(
+                "limit={}..{} has to be a range of at least 1",
+                bounds.min, bounds.max
+            );
+            diagnostics.push(Diagnostic::error(message, modifier.loc));
+        } else if !(bounds.min..=bounds.max).contains(&bounds.default) {
+            let message = format!(
+                "default {} is outside of limit={}..{}",
+                bounds.default, bounds.min, bounds.max
+            );
+            diagnostics.push(Diagnostic::error(message, modifier.loc));
+        }
+        (bounds, diagnostics)
+    }
+}
+
+// adds `limit` and `cursor` to the params and `cursor` to the output
+// a query that declares any of these itself is an error, they'd end up different from every other query
+pub fn paginate(query: &mut AtpQuery, bounds: Bounds) -> Vec<String> {
+    let mut errors = Vec::new();
+    let params = query.parameters.get_or_insert_with(AtpParams::new);
+    for name in ["limit", "cursor"] {
+        if params.properties.contains_key(name) {
+            errors.push(format!(
+                "`{name}` is added by `paginated`, remove it from the params"
+            ));
+        }
+    }
+    if errors.is_empty() {
+        let mut limit = AtpInteger::new();
+        limit.minimum = Some(bounds.min);
+        limit.maximum = Some(bounds.max);
+        limit.default = Some(bounds.default);
+        params
+            .properties
+            .insert("limit".to_string(), ParamProps::Integer(limit));
+        params
+            .properties
+            .insert("cursor".to_string(), ParamProps::String(AtpString::new()));
+    }
+
+    let output = query.output.get_or_insert_with(|| RpcIO {
+        description: None,
+        encoding: "application/json".to_string(),
+        schema: None,
+    });
+    let object = match output
+        .schema
+        .get_or_insert_with(|| RpcSchema::Object(AtpObject::new()))
+    {
+        RpcSchema::Object(object) => object,
+        _ => {
+            errors.push("`paginated` needs an output object to put the `cursor` in".to_string());
+            return errors;
+        }
+    };
+    if object.properties.contains_key("cursor") {
+        errors.push("`cursor` is added by `paginated`, remove it from the output".to_string());
+    } else {
+        // the cursor goes first, the way the atproto lexicons do it
+        object
+            .properties
+            .shift_insert(0, "cursor".to_string(), AtpTypes::String(AtpString::new()));
+    }
+    errors
+}
+
+// a query written without `paginated` that has a `limit` or a `cursor` is probably paginated by hand
+// anything that's missing or looks different from what `paginated` would add is reported
+pub fn lint(query: &AtpQuery) -> Vec<String> {
+    let properties = query.parameters.as_ref().map(|x| &x.properties);
+    let param = |name| properties.and_then(|x| x.get(name));
+    let (limit, cursor) = (param("limit"), param("cursor"));
+    if limit.is_none() && cursor.is_none() {
+        return Vec::new();
+    }
+
+    let mut problems = Vec::new();
+    match limit {
+        None => problems.push("there's a `cursor` param but no `limit`".to_string()),
+        Some(ParamProps::Integer(x))
+            if x.minimum.is_some() && x.maximum.is_some() && x.default.is_some() => {}
+        Some(ParamProps::Integer(_)) => problems.push(
+            "`limit` should have a range and a default, e.g. `Integer(range=1..100, default=50)`"
+                .to_string(),
+        ),
+        Some(_) => problems.push("`limit` should be an Integer".to_string()),
+    }
+    match cursor {
+        None => problems.push("there's a `limit` param but no `cursor`".to_string()),
+        Some(ParamProps::String(_)) => {
+            let output = query.output.as_ref().and_then(|x| x.schema.as_ref());
+            let returned = match output {
+                Some(RpcSchema::Object(x)) => x.properties.contains_key("cursor"),
+                // can't see into refs from here
+                Some(_) => true,
+                None => false,
+            };
+            if !returned {
+                problems.push("the output has no `cursor` for the next page".to_string());
+            }
+        }
+        Some(_) => problems.push("`cursor` should be a String".to_string()),
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(json: &str) -> AtpQuery {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn adds_params_and_cursor() {
+        let mut q = query(
+            r#"{ "parameters": { "type": "params", "properties": { "actor": { "type": "string" } } },
+                 "output": { "encoding": "application/json", "schema": { "type": "object", "properties": {
+                     "feed": { "type": "array", "items": { "type": "string" } }
+                 } } } }"#,
+        );
+        assert!(paginate(&mut q, Bounds::default()).is_empty());
+        let params = q.parameters.as_ref().unwrap();
+        assert_eq!(
+            params.properties.keys().collect::<Vec<_>>(),
+            ["actor", "limit", "cursor"]
+        );
+        let ParamProps::Integer(limit) = &params.properties["limit"] else {
+            panic!("limit should be an integer");
+        };
+        assert_eq!(
+            (limit.minimum, limit.maximum, limit.default),
+            (Some(1), Some(100), Some(50))
+        );
+        let Some(RpcSchema::Object(output)) = &q.output.as_ref().unwrap().schema else {
+            panic!("output should be an object");
+        };
+        assert_eq!(
+            output.properties.keys().collect::<Vec<_>>(),
+            ["cursor", "feed"]
+        );
+        // a paginated query passes its own lint
+        assert!(lint(&q).is_empty());
+    }
+
+    #[test]
+    fn declared_twice() {
+        let mut q = query(
+            r#"{ "parameters": { "type": "params", "properties": { "limit": { "type": "integer" } } } }"#,
+        );
+        assert_eq!(
+            paginate(&mut q, Bounds::default()),
+            ["`limit` is added by `paginated`, remove it from the params"]
+        );
+    }
+
+    #[test]
+    fn lints_hand_written() {
+        let q = query(
+            r#"{ "parameters": { "type": "params", "properties": {
+                "limit": { "type": "integer", "maximum": 100 },
+                "cursor": { "type": "string" }
+            } } }"#,
+        );
+        assert_eq!(
+            lint(&q),
+            [
+                "`limit` should have a range and a default, e.g. `Integer(range=1..100, default=50)`",
+                "the output has no `cursor` for the next page",
+            ]
+        );
+        assert_eq!(
+            lint(&query(
+                r#"{ "parameters": { "type": "params", "properties": { "limit": { "type": "integer" } } } }"#
+            )),
+            [
+                "`limit` should have a range and a default, e.g. `Integer(range=1..100, default=50)`",
+                "there's a `limit` param but no `cursor`",
+            ]
+        );
+        assert!(lint(&query("{}")).is_empty());
+    }
+}
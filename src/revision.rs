@@ -0,0 +1,169 @@
+use lexicon::Lexicon;
+
+use crate::diff::{self, Change, Compat};
+
+// sets the revision of a freshly compiled lexicon based on the last published one
+// unchanged content keeps the published revision, anything else bumps it
+pub fn bump(published: Option<&Lexicon>, lexicon: &mut Lexicon) -> Vec<Change> {
+    let Some(published) = published else {
+        lexicon.revision = Some(1);
+        return Vec::new();
+    };
+    let changes = diff::diff(published, lexicon);
+    lexicon.revision = diff::suggest_revision(published, &changes);
+    changes
+}
+
+// puts an entry for the lexicon's current revision on top of its changelog
+// the revision isn't published until it's in the published dir, so rebuilding it replaces
+// its entry instead of adding another one, and entries past it are from builds that were undone
+pub fn changelog(existing: Option<&str>, lexicon: &Lexicon, changes: &[Change]) -> String {
+    let revision = lexicon.revision.unwrap_or_default();
+    let title = format!("# {}\n", lexicon.id);
+    let rest = existing
+        .map(|x| x.strip_prefix(&title).unwrap_or(x))
+        .unwrap_or_default();
+    // an unchanged lexicon past its first revision keeps the entry it was published with
+    let written = !changes.is_empty() || revision == 1;
+    let kept: String = entries(rest)
+        .into_iter()
+        .filter(|(x, _)| x.is_none_or(|x| x < revision || (x == revision && !written)))
+        .map(|(_, x)| x)
+        .collect();
+    if !written {
+        return format!("{title}{kept}");
+    }
+
+    let mut entry = format!("\n## revision {revision}\n\n");
+    if changes.is_empty() {
+        entry.push_str("- initial revision\n");
+    }
+    for change in changes {
+        let compat = match change.compat {
+            Compat::Breaking => "breaking",
+            Compat::Compatible => "compatible",
+        };
+        let path = if change.path.is_empty() {
+            String::new()
+        } else {
+            format!("`{}`: ", change.path)
+        };
+        entry.push_str(&format!("- {compat}: {path}{}\n", change.message));
+    }
+    format!("{title}{entry}{kept}")
+}
+
+// the changelog split at its `## revision N` headings, anything before the first one has no revision
+fn entries(log: &str) -> Vec<(Option<i32>, &str)> {
+    let mut starts: Vec<usize> = log
+        .match_indices("\n## revision ")
+        .map(|(i, _)| i)
+        .collect();
+    if starts.first() != Some(&0) {
+        starts.insert(0, 0);
+    }
+    starts
+        .iter()
+        .zip(starts.iter().skip(1).chain([&log.len()]))
+        .map(|(&start, &end)| {
+            let entry = &log[start..end];
+            let revision = entry
+                .strip_prefix("\n## revision ")
+                .and_then(|x| x.lines().next())
+                .and_then(|x| x.trim().parse().ok());
+            (revision, entry)
+        })
+        .filter(|(_, x)| !x.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lexicon(max_length: u32) -> Lexicon {
+        serde_json::from_str(&format!(
+            r#"{{ "lexicon": 1, "id": "com.example.test", "defs": {{
+                "main": {{ "type": "string", "maxLength": {max_length} }}
+            }} }}"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn first_publish() {
+        let mut new = lexicon(10);
+        assert!(bump(None, &mut new).is_empty());
+        assert_eq!(new.revision, Some(1));
+    }
+
+    #[test]
+    fn unchanged() {
+        let mut published = lexicon(10);
+        published.revision = Some(3);
+        let mut new = lexicon(10);
+        assert!(bump(Some(&published), &mut new).is_empty());
+        assert_eq!(new.revision, Some(3));
+    }
+
+    #[test]
+    fn changed() {
+        let mut published = lexicon(10);
+        published.revision = Some(3);
+        let mut new = lexicon(20);
+        let changes = bump(Some(&published), &mut new);
+        assert!(!diff::is_breaking(&changes));
+        assert_eq!(new.revision, Some(4));
+
+        let mut new = lexicon(5);
+        let changes = bump(Some(&published), &mut new);
+        assert!(diff::is_breaking(&changes));
+        assert_eq!(new.revision, Some(4));
+    }
+
+    #[test]
+    fn changelog_entries() {
+        let mut first = lexicon(10);
+        let changes = bump(None, &mut first);
+        let log = changelog(None, &first, &changes);
+        assert_eq!(
+            log,
+            "# com.example.test\n\n## revision 1\n\n- initial revision\n"
+        );
+
+        let mut second = lexicon(20);
+        let changes = bump(Some(&first), &mut second);
+        let log = changelog(Some(&log), &second, &changes);
+        assert_eq!(
+            log,
+            "# com.example.test\n\n## revision 2\n\n- compatible: `main`: maxLength widened from 10 to 20\n\n## revision 1\n\n- initial revision\n"
+        );
+    }
+
+    #[test]
+    fn rebuilt_revision() {
+        let mut first = lexicon(10);
+        bump(None, &mut first);
+        let log = changelog(None, &first, &[]);
+
+        // built twice against the same published revision, e.g. with --watch
+        let mut second = lexicon(20);
+        let changes = bump(Some(&first), &mut second);
+        let log = changelog(Some(&log), &second, &changes);
+        let mut again = lexicon(30);
+        let changes = bump(Some(&first), &mut again);
+        let log = changelog(Some(&log), &again, &changes);
+        assert_eq!(
+            log,
+            "# com.example.test\n\n## revision 2\n\n- compatible: `main`: maxLength widened from 10 to 30\n\n## revision 1\n\n- initial revision\n"
+        );
+
+        // and changed back, revision 2 never happened
+        let mut undone = lexicon(10);
+        let changes = bump(Some(&first), &mut undone);
+        assert_eq!(
+            changelog(Some(&log), &undone, &changes),
+            "# com.example.test\n\n## revision 1\n\n- initial revision\n"
+        );
+    }
+}
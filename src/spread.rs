@@ -0,0 +1,362 @@
+use ir::diagnostic::Diagnostic;
+use lexicon::{AtpObject, AtpTypes, Catalog, Lexicon, RpcIO, RpcSchema};
+use tree_sitter::Range;
+
+// `...#common` inside the object of def `into`, found while lowering and applied once every def is in
+#[derive(Debug)]
+pub struct Spread {
+    pub into: String,
+    // `input` or `output` when `into` is a query or procedure
+    pub section: Option<String>,
+    pub reference: String,
+    // how many properties came before the spread, the spread ones go in there
+    pub position: usize,
+    pub loc: Range,
+}
+
+// copies the properties of every spread object into the def it was spread into
+// spreads of spreads are applied first, so the order of defs in the file doesn't matter
+pub fn apply(lexicon: &mut Lexicon, catalog: &Catalog, spreads: &[Spread]) -> Vec<Diagnostic> {
+    let mut s = Spreader {
+        lexicon,
+        catalog,
+        spreads,
+        done: Vec::new(),
+        stack: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+    for spread in spreads {
+        s.spread_into(&spread.into);
+    }
+    s.diagnostics
+}
+
+struct Spreader<'a> {
+    lexicon: &'a mut Lexicon,
+    catalog: &'a Catalog,
+    spreads: &'a [Spread],
+    done: Vec<String>,
+    stack: Vec<String>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Spreader<'_> {
+    fn spread_into(&mut self, target: &str) {
+        if self.done.iter().any(|x| x == target) {
+            return;
+        }
+        self.stack.push(target.to_string());
+        let spreads = self.spreads;
+        let mut inserted = 0;
+        for spread in spreads.iter().filter(|x| x.into == target) {
+            match self.source(spread) {
+                Ok(source) => inserted += self.merge(spread, source, spread.position + inserted),
+                Err(message) => self
+                    .diagnostics
+                    .push(Diagnostic::error(message, spread.loc)),
+            }
+        }
+        self.stack.pop();
+        self.done.push(target.to_string());
+    }
+
+    // the object being spread, refs in objects from other lexicons are made absolute
+    fn source(&mut self, spread: &Spread) -> Result<AtpObject, String> {
+        let reference = &spread.reference;
+        if let Some(local) = reference.strip_prefix('#') {
+            if self.stack.iter().any(|x| x == local) {
+                return Err(format!("`{reference}` ends up spreading itself"));
+            }
+            self.spread_into(local);
+            return match self.lexicon.defs.get(local) {
+                Some(def) => object(reference, def).cloned(),
+                None => Err(format!("`{reference}` isn't defined")),
+            };
+        }
+
+        let (nsid, _) = reference.split_once('#').unwrap_or((reference, "main"));
+        if !self.catalog.contains(nsid) {
+            return Err(format!(
+                "can't spread `{reference}`, {nsid} isn't loaded, see `--lexicons`"
+            ));
+        }
+        let Some(def) = self.catalog.resolve(reference) else {
+            return Err(format!("`{reference}` doesn't exist in {nsid}"));
+        };
+        let mut source = object(reference, def)?.clone();
+        for prop in source.properties.values_mut() {
+            qualify(prop, nsid);
+        }
+        Ok(source)
+    }
+
+    // returns how many properties were added, the ones `into` already has must match exactly
+    fn merge(&mut self, spread: &Spread, source: AtpObject, position: usize) -> usize {
+        let section = spread.section.as_deref();
+        let target = self.lexicon.defs.get_mut(&spread.into);
+        let Some(target) = target.and_then(|x| object_mut(x, section)) else {
+            self.diagnostics.push(Diagnostic::error(
+                format!("`{}` has nowhere to go", spread.reference),
+                spread.loc,
+            ));
+            return 0;
+        };
+        let listed = |list: &Option<Vec<String>>, name: &str| {
+            list.as_ref().is_some_and(|x| x.iter().any(|x| x == name))
+        };
+
+        let mut inserted = 0;
+        for (name, prop) in source.properties {
+            let required = listed(&source.required, &name);
+            let nullable = listed(&source.nullable, &name);
+            if let Some(existing) = target.properties.get(&name) {
+                let same = *existing == prop
+                    && listed(&target.required, &name) == required
+                    && listed(&target.nullable, &name) == nullable;
+                if !same {
+                    self.diagnostics.push(Diagnostic::error(
+                        format!(
+                            "`{name}` from `{}` conflicts with the `{name}` already in {}",
+                            spread.reference,
+                            target_name(spread)
+                        ),
+                        spread.loc,
+                    ));
+                }
+                continue;
+            }
+            let index = (position + inserted).min(target.properties.len());
+            target.properties.shift_insert(index, name.clone(), prop);
+            if required {
+                target.required.get_or_insert_default().push(name.clone());
+            }
+            if nullable {
+                target.nullable.get_or_insert_default().push(name);
+            }
+            inserted += 1;
+        }
+        inserted
+    }
+}
+
+fn object<'a>(reference: &str, def: &'a AtpTypes) -> Result<&'a AtpObject, String> {
+    match def {
+        AtpTypes::Object(x) => Ok(x),
+        AtpTypes::Record(x) => Ok(&x.record),
+        other => Err(format!(
+            "`{reference}` is a {}, only objects and records can be spread",
+            other.kind()
+        )),
+    }
+}
+
+fn object_mut<'a>(def: &'a mut AtpTypes, section: Option<&str>) -> Option<&'a mut AtpObject> {
+    let io = match (def, section) {
+        (AtpTypes::Object(x), None) => return Some(x),
+        (AtpTypes::Record(x), None) => return Some(&mut x.record),
+        (AtpTypes::Query(x), Some("output")) => &mut x.output,
+        (AtpTypes::Procedure(x), Some("input")) => &mut x.input,
+        (AtpTypes::Procedure(x), Some("output")) => &mut x.output,
+        _ => return None,
+    };
+    match io {
+        Some(RpcIO {
+            schema: Some(RpcSchema::Object(x)),
+            ..
+        }) => Some(x),
+        _ => None,
+    }
+}
+
+// "`main`" or "the output of `getFeed`"
+fn target_name(spread: &Spread) -> String {
+    match &spread.section {
+        Some(section) => format!("the {section} of `{}`", spread.into),
+        None => format!("`{}`", spread.into),
+    }
+}
+
+// `#foo` means something else once it's copied out of `nsid`
+fn qualify(t: &mut AtpTypes, nsid: &str) {
+    let absolute = |x: &mut String| {
+        if x.starts_with('#') {
+            *x = format!("{nsid}{x}");
+        }
+    };
+    match t {
+        AtpTypes::Ref(x) => absolute(&mut x.reference),
+        AtpTypes::Union(x) => x.refs.iter_mut().for_each(absolute),
+        AtpTypes::Array(x) => qualify(&mut x.items, nsid),
+        AtpTypes::Object(x) => x.properties.values_mut().for_each(|x| qualify(x, nsid)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn lexicon(defs: &str) -> Lexicon {
+        serde_json::from_str(&format!(
+            r#"{{ "lexicon": 1, "id": "com.example.test", "defs": {{ {defs} }} }}"#
+        ))
+        .unwrap()
+    }
+
+    fn spread(into: &str, reference: &str, position: usize) -> Spread {
+        Spread {
+            into: into.to_string(),
+            section: None,
+            reference: reference.to_string(),
+            position,
+            loc: Range {
+                start_byte: 0,
+                end_byte: 0,
+                start_point: Default::default(),
+                end_point: Default::default(),
+            },
+        }
+    }
+
+    fn properties(lexicon: &Lexicon, def: &str) -> Vec<String> {
+        let AtpTypes::Object(object) = &lexicon.defs[def] else {
+            panic!("{def} isn't an object");
+        };
+        object.properties.keys().cloned().collect()
+    }
+
+    #[test]
+    fn local() {
+        let mut lexicon = lexicon(
+            r#"
+            "main": { "type": "object", "required": ["text"], "properties": {
+                "text": { "type": "string" }
+            } },
+            "common": { "type": "object", "required": ["createdAt"], "nullable": ["langs"], "properties": {
+                "createdAt": { "type": "string", "format": "datetime" },
+                "langs": { "type": "array", "items": { "type": "string" } }
+            } }
+            "#,
+        );
+        let diagnostics = apply(
+            &mut lexicon,
+            &Catalog::new(),
+            &[spread("main", "#common", 0)],
+        );
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+        assert_eq!(properties(&lexicon, "main"), ["createdAt", "langs", "text"]);
+        let AtpTypes::Object(main) = &lexicon.defs["main"] else {
+            unreachable!()
+        };
+        assert_eq!(
+            main.required,
+            Some(vec!["text".to_string(), "createdAt".to_string()])
+        );
+        assert_eq!(main.nullable, Some(vec!["langs".to_string()]));
+    }
+
+    #[test]
+    fn chained_and_cyclic() {
+        let mut lexicon = lexicon(
+            r#"
+            "main": { "type": "object", "properties": {} },
+            "a": { "type": "object", "properties": { "x": { "type": "integer" } } },
+            "b": { "type": "object", "properties": {} },
+            "c": { "type": "object", "properties": {} }
+            "#,
+        );
+        let diagnostics = apply(
+            &mut lexicon,
+            &Catalog::new(),
+            &[
+                spread("main", "#b", 0),
+                spread("b", "#a", 0),
+                spread("c", "#c", 0),
+            ],
+        );
+        assert_eq!(properties(&lexicon, "main"), ["x"]);
+        let messages: Vec<&str> = diagnostics.iter().map(|x| x.message.as_str()).collect();
+        assert_eq!(messages, ["`#c` ends up spreading itself"]);
+    }
+
+    #[test]
+    fn conflicts() {
+        let mut lexicon = lexicon(
+            r#"
+            "main": { "type": "object", "properties": {
+                "createdAt": { "type": "string" },
+                "text": { "type": "string" }
+            } },
+            "common": { "type": "object", "properties": {
+                "createdAt": { "type": "string", "format": "datetime" },
+                "text": { "type": "string" }
+            } },
+            "token": { "type": "token" }
+            "#,
+        );
+        let diagnostics = apply(
+            &mut lexicon,
+            &Catalog::new(),
+            &[
+                spread("main", "#common", 0),
+                spread("main", "#token", 0),
+                spread("main", "#missing", 0),
+            ],
+        );
+        let messages: Vec<&str> = diagnostics.iter().map(|x| x.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "`createdAt` from `#common` conflicts with the `createdAt` already in `main`",
+                "`#token` is a token, only objects and records can be spread",
+                "`#missing` isn't defined",
+            ]
+        );
+    }
+
+    #[test]
+    fn other_lexicons() {
+        let mut catalog = Catalog::new();
+        catalog
+            .load_str(
+                Path::new("defs.json"),
+                r##"{ "lexicon": 1, "id": "com.example.defs", "defs": {
+                    "common": { "type": "object", "properties": {
+                        "labels": { "type": "union", "refs": ["#selfLabels"] },
+                        "image": { "type": "ref", "ref": "com.example.image" }
+                    } },
+                    "selfLabels": { "type": "object", "properties": {} }
+                } }"##,
+            )
+            .unwrap();
+        let mut lexicon = lexicon(r#""main": { "type": "object", "properties": {} }"#);
+        let diagnostics = apply(
+            &mut lexicon,
+            &catalog,
+            &[
+                spread("main", "com.example.defs#common", 0),
+                spread("main", "com.example.other#common", 0),
+            ],
+        );
+        let messages: Vec<&str> = diagnostics.iter().map(|x| x.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "can't spread `com.example.other#common`, com.example.other isn't loaded, see `--lexicons`"
+            ]
+        );
+        let AtpTypes::Object(main) = &lexicon.defs["main"] else {
+            unreachable!()
+        };
+        let AtpTypes::Union(labels) = &main.properties["labels"] else {
+            panic!("labels should be a union");
+        };
+        assert_eq!(labels.refs, ["com.example.defs#selfLabels"]);
+        let AtpTypes::Ref(image) = &main.properties["image"] else {
+            panic!("image should be a ref");
+        };
+        assert_eq!(image.reference, "com.example.image");
+    }
+}
@@ -0,0 +1,245 @@
+// tree-sitter grammar for ana, the README has what each construct turns into
+
+const commaSep1 = (rule) => seq(rule, repeat(seq(",", rule)), optional(","));
+const commaSep = (rule) => optional(commaSep1(rule));
+
+module.exports = grammar({
+  name: "ana",
+
+  extras: ($) => [/\s/, $.comment],
+
+  word: ($) => $.identifier,
+
+  // `Page<T> { ... }` and `Page<T>` only part ways at the `{`, and only inside a test harness
+  conflicts: ($) => [[$._generics, $.type]],
+
+  rules: {
+    // `@@[ ... ]@@` holds a single node for tests to pick up with `child(1)`
+    source_file: ($) =>
+      repeat(choice($.namespace, seq("@@[", $._fragment, "]@@"))),
+
+    _fragment: ($) =>
+      choice(
+        $._def,
+        $.property,
+        $.optional,
+        $.spread,
+        $.param,
+        $._type,
+        $.string,
+        $.integer,
+        $.boolean,
+        $.slice,
+        $.list,
+      ),
+
+    namespace: ($) =>
+      seq("@", field("name", $.nsid), "{", repeat(field("body", $._def)), "}"),
+
+    _def: ($) =>
+      choice(
+        $.object,
+        $.record,
+        $.token,
+        $.alias,
+        $.query,
+        $.procedure,
+        $.errors,
+      ),
+
+    // `imageLink { ... }`, or `Page<T> { items: T[..100] }` when it takes type params
+    object: ($) =>
+      seq(
+        field("name", $.identifier),
+        optional($._generics),
+        field("body", $.object_body),
+      ),
+
+    // `record main { ... }` or `record(nsid) main { ... }`
+    record: ($) =>
+      seq(
+        "record",
+        optional(seq("(", field("key", choice($.string, $.record_key)), ")")),
+        field("name", $.identifier),
+        optional($._generics),
+        field("body", $.object_body),
+      ),
+
+    _generics: ($) => seq("<", commaSep1(field("generic", $.identifier)), ">"),
+
+    // `token spam;`
+    token: ($) => seq("token", field("name", $.identifier), optional(";")),
+
+    // `type Tag = String(len=..640);` or `type Image = #image;`
+    alias: ($) =>
+      seq(
+        "type",
+        field("name", $.identifier),
+        "=",
+        field("type", choice($.type, $.ref)),
+        optional(";"),
+      ),
+
+    // `paginated(limit=1..50) query getFeed { params { ... } output { ... } }`
+    query: ($) =>
+      seq(
+        optional(field("modifier", alias($.modifier, $.type))),
+        "query",
+        field("name", $.identifier),
+        $._rpc_body,
+      ),
+
+    // `procedure createPost { input { ... } output { ... } errors { ... } }`
+    procedure: ($) =>
+      seq(
+        optional(field("modifier", alias($.modifier, $.type))),
+        "procedure",
+        field("name", $.identifier),
+        $._rpc_body,
+      ),
+
+    // written like a type so its bounds are params like any other
+    modifier: ($) =>
+      seq(field("name", alias("paginated", $.identifier)), optional($._params)),
+
+    _rpc_body: ($) =>
+      seq(
+        "{",
+        repeat(
+          choice(
+            seq("params", field("params", $.object_body)),
+            seq("input", field("input", $.object_body)),
+            seq("output", field("output", $.object_body)),
+            seq("errors", field("errors", $.errors_body)),
+          ),
+        ),
+        "}",
+      ),
+
+    // `errors RepoErrors { RepoNotFound; RepoTakendown: "gone"; }`
+    errors: ($) =>
+      seq("errors", field("name", $.identifier), field("body", $.errors_body)),
+
+    errors_body: ($) => seq("{", repeat(choice($.error, $.spread)), "}"),
+
+    error: ($) =>
+      seq(
+        field("name", $.identifier),
+        optional(seq(":", field("description", $.string))),
+        optional(";"),
+      ),
+
+    object_body: ($) =>
+      seq("{", repeat(choice($.property, $.optional, $.spread)), "}"),
+
+    property: ($) =>
+      seq(
+        field("name", $.identifier),
+        ":",
+        field("type", $._type),
+        optional(";"),
+      ),
+
+    optional: ($) =>
+      seq(
+        field("name", $.identifier),
+        "?",
+        ":",
+        field("type", $._type),
+        optional(";"),
+      ),
+
+    // `...#common` in objects, `...RepoErrors` in error lists
+    spread: ($) => seq("...", choice($.ref, $.identifier), optional(";")),
+
+    _type: ($) => choice($.type, $.ref, $.object_body),
+
+    // `String(len=..640)[..10]`, `Page<#post>` or `#post[..50]`
+    type: ($) =>
+      choice(
+        seq(
+          field("name", $.identifier),
+          optional(seq("<", commaSep1(field("argument", $._type)), ">")),
+          optional($._params),
+          optional($._length),
+        ),
+        seq(field("name", $.ref), $._length),
+      ),
+
+    _params: ($) => seq("(", commaSep(field("param", $.param)), ")"),
+
+    // the brackets of an array, its length goes on the type node itself
+    _length: ($) =>
+      seq(
+        "[",
+        optional(
+          seq(
+            optional(field("min", $.integer)),
+            "..",
+            optional(field("max", $.integer)),
+          ),
+        ),
+        "]",
+      ),
+
+    param: ($) =>
+      seq(field("name", $.identifier), "=", field("value", $._value)),
+
+    _value: ($) =>
+      choice(
+        $.string,
+        $.integer,
+        $.boolean,
+        $.slice,
+        $.ref,
+        $.identifier,
+        $.list,
+      ),
+
+    // `[#foo, "bar"]`
+    list: ($) => seq("[", commaSep($._value), "]"),
+
+    // `1..2`, `..640` or `10..`
+    slice: ($) =>
+      choice(
+        seq(field("min", $.integer), "..", optional(field("max", $.integer))),
+        seq("..", field("max", $.integer)),
+        "..",
+      ),
+
+    boolean: (_) => choice("true", "false"),
+
+    // `42`, `-5`, `1_000_000`, `0xff` or `1MiB`, the ir checks the digits and units
+    integer: (_) => token(/-?(0[xX][0-9a-fA-F_]+|[0-9][0-9_]*[A-Za-z]*)/),
+
+    // `"a \"b\""`, `r"C:\raw"`, `r#"say "hi""#` and `"""` blocks, the ir resolves escapes
+    string: (_) =>
+      token(
+        choice(
+          /"([^"\\\n]|\\.)*"/,
+          /"""([^"]|"[^"]|""[^"])*"""/,
+          /r"[^"]*"/,
+          /r#"([^"]|"+[^"#])*"+#/,
+          /r##"([^"]|"+[^"#]|"+#[^"#])*"+##/,
+        ),
+      ),
+
+    // `#image`, `com.atproto.repo.strongRef` or `com.example.defs#common`
+    ref: (_) =>
+      token(
+        choice(
+          /#[A-Za-z_][A-Za-z0-9_]*/,
+          /[A-Za-z][A-Za-z0-9-]*(\.[A-Za-z0-9-]+)+(#[A-Za-z_][A-Za-z0-9_]*)?/,
+        ),
+      ),
+
+    nsid: (_) => /[A-Za-z][A-Za-z0-9-]*(\.[A-Za-z0-9-]+)+/,
+
+    // `tid`, `nsid`, `any` or `literal:self`
+    record_key: (_) => /[a-z]+(:[A-Za-z0-9._:~-]+)?/,
+
+    identifier: (_) => /[A-Za-z_][A-Za-z0-9_]*/,
+
+    comment: (_) => token(seq("//", /[^\n]*/)),
+  },
+});
@@ -0,0 +1,260 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+use notify::{EventKind, RecursiveMode, Watcher};
+use tree_sitter::Node;
+
+use crate::{Cli, Emit, Output, compile, parser};
+
+// how long to wait for more events after the first one, editors tend to save in bursts
+const SETTLE: Duration = Duration::from_millis(100);
+
+// what a source file declared the last time it was built
+struct Entry {
+    nsid: String,
+    deps: HashSet<String>,
+}
+
+pub fn watch(cli: &mut Cli, paths: &[PathBuf], output: &Output, emit: &[Emit]) {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = match notify::recommended_watcher(tx) {
+        Ok(watcher) => watcher,
+        Err(e) => return cli.trouble(Path::new("."), &e.to_string()),
+    };
+    for path in paths {
+        if let Err(e) = watcher.watch(path, RecursiveMode::Recursive) {
+            return cli.trouble(path, &e.to_string());
+        }
+    }
+
+    let mut graph: HashMap<PathBuf, Entry> = HashMap::new();
+    let sources = cli.sources(paths, &["ana"]);
+    for path in &sources {
+        load(cli, path);
+    }
+    for path in sources {
+        build(cli, &mut graph, &path, output, emit);
+    }
+    cli.info("watching for changes");
+
+    while let Ok(event) = rx.recv() {
+        let mut changed = HashSet::new();
+        let mut event = Some(event);
+        while let Some(e) = event {
+            // building reads the files, which would otherwise set off another build
+            if let Ok(e) = e
+                && !matches!(e.kind, EventKind::Access(_))
+            {
+                changed.extend(
+                    e.paths
+                        .iter()
+                        .filter(|x| x.extension().is_some_and(|x| x == "ana"))
+                        .map(|x| key(x)),
+                );
+            }
+            event = rx.recv_timeout(SETTLE).ok();
+        }
+        if changed.is_empty() {
+            continue;
+        }
+
+        // a file's old nsid counts as changed too, in case it was renamed or removed
+        let mut nsids: Vec<String> = changed
+            .iter()
+            .filter_map(|x| graph.get(x).map(|x| x.nsid.clone()))
+            .collect();
+        for nsid in &nsids {
+            if cli.catalog.path(nsid).is_some_and(|x| changed.contains(x)) {
+                cli.catalog.remove(nsid);
+            }
+        }
+        for path in changed.iter().filter(|x| !x.exists()) {
+            graph.remove(path);
+            cli.info(&format!("{} was removed", path.display()));
+        }
+
+        let mut affected: Vec<PathBuf> = changed.into_iter().filter(|x| x.exists()).collect();
+        for path in &affected {
+            load(cli, path);
+        }
+        for path in affected.clone() {
+            if let Some(entry) = build(cli, &mut graph, &path, output, emit) {
+                nsids.push(entry);
+            }
+        }
+
+        for path in dependents(&graph, nsids, &affected) {
+            build(cli, &mut graph, &path, output, emit);
+            affected.push(path);
+        }
+        cli.info(&format!("rebuilt {} file(s)", affected.len()));
+    }
+}
+
+// compiles a file without reporting anything and puts it in the catalog, so the files built after it
+// can resolve refs and spreads into it, even when they're built first
+fn load(cli: &mut Cli, path: &Path) {
+    let Ok(src) = fs::read_to_string(path) else {
+        return;
+    };
+    let tree = parser().parse(&src, None).unwrap();
+    let (lexicon, _) = compile::compile(&src, &tree, &cli.catalog);
+    if !lexicon.id.is_empty() {
+        cli.catalog.replace(&key(path), lexicon);
+    }
+}
+
+// builds a file and updates the graph and the catalog, returns the nsid it declares
+fn build(
+    cli: &mut Cli,
+    graph: &mut HashMap<PathBuf, Entry>,
+    path: &Path,
+    output: &Output,
+    emit: &[Emit],
+) -> Option<String> {
+    let lexicon = cli.build_file(path, Some(output), emit)?;
+    if !lexicon.id.is_empty() {
+        cli.catalog.replace(&key(path), lexicon.clone());
+    }
+    // spreads are copied in and leave no ref behind, the source still has them
+    let mut deps = HashSet::new();
+    if let Ok(src) = fs::read_to_string(path) {
+        let tree = parser().parse(&src, None).unwrap();
+        refs(&src, &tree.root_node(), &mut deps);
+    }
+    deps.extend(
+        lexicon
+            .references()
+            .into_iter()
+            .filter(|x| !x.starts_with('#'))
+            .map(|x| x.split('#').next().unwrap_or(x).to_string()),
+    );
+    graph.insert(
+        key(path),
+        Entry {
+            nsid: lexicon.id.clone(),
+            deps,
+        },
+    );
+    Some(lexicon.id)
+}
+
+// the nsids of every ref written in the source that points outside of it
+fn refs(src: &str, node: &Node, out: &mut HashSet<String>) {
+    if node.kind() == "ref" {
+        let text = &src[node.byte_range()];
+        if !text.starts_with('#') {
+            out.insert(text.split('#').next().unwrap_or(text).to_string());
+        }
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        refs(src, &child, out);
+    }
+}
+
+// the graph is keyed by canonical paths, notify reports paths as they were watched
+// a removed file can't be canonicalized, its directory still can
+fn key(path: &Path) -> PathBuf {
+    if let Ok(path) = path.canonicalize() {
+        return path;
+    }
+    match (path.parent().map(|x| x.canonicalize()), path.file_name()) {
+        (Some(Ok(dir)), Some(name)) => dir.join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+// every file that depends on one of the nsids, directly or through other files
+fn dependents(
+    graph: &HashMap<PathBuf, Entry>,
+    nsids: Vec<String>,
+    skip: &[PathBuf],
+) -> Vec<PathBuf> {
+    let mut seen: HashSet<&PathBuf> = skip.iter().collect();
+    let mut found = Vec::new();
+    let mut queue = nsids;
+
+    while let Some(nsid) = queue.pop() {
+        let mut hits: Vec<(&PathBuf, &Entry)> = graph
+            .iter()
+            .filter(|(path, entry)| !seen.contains(path) && entry.deps.contains(&nsid))
+            .collect();
+        hits.sort_by_key(|(path, _)| *path);
+        for (path, entry) in hits {
+            seen.insert(path);
+            found.push(path.clone());
+            queue.push(entry.nsid.clone());
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(nsid: &str, deps: &[&str]) -> Entry {
+        Entry {
+            nsid: nsid.to_string(),
+            deps: deps.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn transitive_dependents() {
+        let graph = HashMap::from([
+            (PathBuf::from("a.ana"), entry("com.example.a", &[])),
+            (
+                PathBuf::from("b.ana"),
+                entry("com.example.b", &["com.example.a"]),
+            ),
+            (
+                PathBuf::from("c.ana"),
+                entry("com.example.c", &["com.example.b"]),
+            ),
+            (
+                PathBuf::from("d.ana"),
+                entry("com.example.d", &["com.example.c"]),
+            ),
+            (PathBuf::from("e.ana"), entry("com.example.e", &[])),
+        ]);
+        let found = dependents(
+            &graph,
+            vec!["com.example.a".to_string()],
+            &[PathBuf::from("a.ana")],
+        );
+        assert_eq!(
+            found,
+            vec![
+                PathBuf::from("b.ana"),
+                PathBuf::from("c.ana"),
+                PathBuf::from("d.ana")
+            ]
+        );
+    }
+    #[test]
+    fn spread_refs() {
+        let src = "@com.example.b {\n    main { ...com.example.a#common; b: #local; c: com.example.c; }\n}\n";
+        let tree = parser().parse(src, None).unwrap();
+        let mut found = HashSet::new();
+        refs(src, &tree.root_node(), &mut found);
+        assert_eq!(
+            found,
+            HashSet::from(["com.example.a".to_string(), "com.example.c".to_string()])
+        );
+    }
+
+    #[test]
+    fn keys_removed_files() {
+        let dir = Path::new("src").canonicalize().unwrap();
+        assert_eq!(key(Path::new("src/main.rs")), dir.join("main.rs"));
+        assert_eq!(key(Path::new("src/removed.ana")), dir.join("removed.ana"));
+    }
+}